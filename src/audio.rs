@@ -0,0 +1,102 @@
+use std::{f64::consts::TAU, fs::File, io::{BufWriter, Write}, time::Duration};
+
+use crate::model::{Model, Note, ClockEvents, MAX_VOLUME, STEPS_PER_BEAT};
+
+// The output is raw signed 16 bit little endian mono PCM,
+// e.g. for `aplay -f S16_LE -r 44100` reading from a FIFO.
+pub const SAMPLE_RATE: f64 = 44100.0;
+// Leaves room for a few overlapping voices.
+const HEADROOM: f64 = 0.3;
+// Metronome clicks are short blips, higher on the first beat.
+const CLICK_SECS: f64 = 0.03;
+const CLICK_FREQUENCY: f64 = 1000.0;
+const ACCENT_FREQUENCY: f64 = 1500.0;
+
+fn frequency(note: Note) -> f64
+{
+    match note {
+        Note::A => 440.0,
+        Note::B => 493.88,
+        Note::C => 523.25,
+        Note::D => 587.33,
+        Note::E => 659.25,
+        Note::F => 698.46,
+        Note::G => 783.99,
+    }
+}
+
+struct Voice {
+    frequency: f64,
+    gain: f64,
+    // The sample it starts at, relative to the next one rendered.
+    start: i64,
+    length: i64,
+}
+
+impl Voice {
+    fn sample(&self, index: i64) -> f64
+    {
+        let age = index - self.start;
+        if age < 0 || age >= self.length {
+            return 0.0;
+        }
+        let decay = 1.0 - age as f64 / self.length as f64;
+        (TAU * self.frequency * age as f64 / SAMPLE_RATE).sin() * decay * self.gain
+    }
+}
+
+// Renders the notes of the sequencer. Notes start at the exact
+// sample their step event happened at, not at frame boundaries.
+pub struct AudioEngine {
+    out: BufWriter<File>,
+    voices: Vec<Voice>,
+    // Fractional samples left over from the last period.
+    remainder: f64,
+}
+
+impl AudioEngine {
+    pub fn open(path: &str) -> anyhow::Result<Self>
+    {
+        let out = BufWriter::new(File::create(path)?);
+        Ok(Self { out, voices: vec![], remainder: 0.0 })
+    }
+
+    // Renders the period passed to `Model::update`, along
+    // with the events it returned.
+    pub fn render(&mut self, elapsed: Duration, events: &ClockEvents, model: &Model) -> anyhow::Result<()>
+    {
+        let exact = elapsed.as_secs_f64() * SAMPLE_RATE + self.remainder;
+        let count = exact.floor() as i64;
+        self.remainder = exact - count as f64;
+        let step_length = 60.0 / model.bpm.max(1.0) / STEPS_PER_BEAT * SAMPLE_RATE;
+        for beat in &events.beats {
+            self.voices.push(Voice {
+                frequency: if beat.beat == 0 { ACCENT_FREQUENCY } else { CLICK_FREQUENCY },
+                gain: model.click_level * HEADROOM,
+                start: (beat.at.as_secs_f64() * SAMPLE_RATE) as i64,
+                length: (CLICK_SECS * SAMPLE_RATE) as i64,
+            });
+        }
+        for event in &events.steps {
+            if let Some(note) = event.note {
+                self.voices.push(Voice {
+                    frequency: frequency(note),
+                    gain: model.volume / MAX_VOLUME * HEADROOM,
+                    start: (event.at.as_secs_f64() * SAMPLE_RATE) as i64,
+                    length: step_length as i64,
+                });
+            }
+        }
+        for index in 0..count {
+            let sample: f64 = self.voices.iter().map(|voice| voice.sample(index)).sum();
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f64) as i16;
+            self.out.write_all(&sample.to_le_bytes())?;
+        }
+        self.out.flush()?;
+        for voice in &mut self.voices {
+            voice.start -= count;
+        }
+        self.voices.retain(|voice| voice.start + voice.length > 0);
+        Ok(())
+    }
+}
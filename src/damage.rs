@@ -0,0 +1,91 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use eframe::egui;
+use eframe::epaint::Rect;
+
+// What needs drawing again: the regions of a display that changed,
+// and when views will change without any input, like a timed overlay
+// going away.
+#[derive(Default)]
+pub struct Damage {
+    rects: Vec<Rect>,
+    wake: Option<Instant>,
+}
+
+impl Damage {
+    pub fn add(&mut self, rect: Rect)
+    {
+        if rect.is_positive() {
+            self.rects.push(rect);
+        }
+    }
+
+    // The view changes by itself at the given time.
+    pub fn wake_at(&mut self, at: Instant)
+    {
+        self.wake = Some(self.wake.map_or(at, |wake| wake.min(at)));
+    }
+
+    pub fn rects(&self) -> &[Rect]
+    {
+        &self.rects
+    }
+
+    pub fn wake(&self) -> Option<Instant>
+    {
+        self.wake
+    }
+}
+
+// Remembers the state a view was drawn with last, to tell
+// when it needs drawing again.
+pub struct Shown<T> {
+    last: Option<T>,
+}
+
+impl<T> Default for Shown<T>
+{
+    fn default() -> Self {
+        Self { last: None }
+    }
+}
+
+impl<T: PartialEq> Shown<T> {
+    // Records the state about to be drawn, true if it differs
+    // from the last one. After forget, that's always the case.
+    pub fn update(&mut self, state: T) -> bool
+    {
+        if self.last.as_ref() == Some(&state) {
+            return false;
+        }
+        self.last = Some(state);
+        true
+    }
+
+    pub fn forget(&mut self)
+    {
+        self.last = None;
+    }
+}
+
+// Wakes the UI up from other threads when there's something new
+// to show. Until the window is up there's nobody to wake.
+#[derive(Clone, Default)]
+pub struct Waker {
+    ctx: Arc<OnceLock<egui::Context>>,
+}
+
+impl Waker {
+    pub fn connect(&self, ctx: &egui::Context)
+    {
+        let _ = self.ctx.set(ctx.clone());
+    }
+
+    pub fn wake(&self)
+    {
+        if let Some(ctx) = self.ctx.get() {
+            ctx.request_repaint();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Vec2, Color32, Rounding, Stroke, vec2}, emath::Align2};
+
+use crate::view::{View, ModalHandle};
+use crate::layout::{self, Place, Anchor};
+use crate::damage::{Damage, Shown};
+use crate::event::Event;
+use crate::model::Model;
+
+const TOAST_TIMEOUT: Duration = Duration::from_millis(1500);
+
+// Questions sit in the middle of the screen, designed for 240x80.
+const DIALOG: Place = Place::new(Anchor::Center, 0.75, 0.4);
+const DIALOG_DESIGN: Vec2 = vec2(240.0, 80.0);
+// Toasts near the bottom, designed for 240x30.
+const TOAST: Place = Place::new(Anchor::Bottom, 0.75, 0.15).offset(0.0, -0.05);
+const TOAST_DESIGN: Vec2 = vec2(240.0, 30.0);
+
+// Asks a yes/no question. Meant to be pushed onto a ModalStack,
+// it pops itself once answered. Back counts as no.
+pub struct ConfirmView {
+    question: String,
+    on_yes: Box<dyn FnMut(&mut Model) + Send>,
+    modals: ModalHandle,
+    rect: Rect,
+    shown: Shown<()>,
+}
+
+impl ConfirmView {
+    pub fn new(question: &str, modals: ModalHandle, on_yes: impl FnMut(&mut Model) + Send + 'static) -> Self
+    {
+        Self { question: question.into(), on_yes: Box::new(on_yes), modals, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+}
+
+impl View for ConfirmView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed(Key::ArrowLeft, event.clone()) {
+            self.modals.pop();
+            true
+        } else if self.pressed(Key::ArrowRight, event) {
+            (self.on_yes)(model);
+            self.modals.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, _model: &Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        let painter = ui.painter_at(rect);
+        let white = Color32::from_gray(255);
+        painter.rect(rect, Rounding::same(4.0), Color32::from_gray(20), Stroke::new(2.0_f32, white));
+        let scale = layout::scale(rect, DIALOG_DESIGN);
+        let font = layout::font(14.0, scale);
+        let rows = layout::rows(rect.shrink2(vec2(16.0, 4.0) * scale), 2, 0.0);
+        painter.text(rows[0].center(), Align2::CENTER_CENTER, &self.question, font.clone(), white);
+        painter.text(rows[1].left_center(), Align2::LEFT_CENTER, "< No", font.clone(), white);
+        painter.text(rows[1].right_center(), Align2::RIGHT_CENTER, "Yes >", font, white);
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = DIALOG.rect(rect);
+    }
+
+    // The question stays the same until answered.
+    fn damage(&mut self, _model: &Model, damage: &mut Damage) {
+        if self.shown.update(()) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+}
+
+// Hands messages to a ToastView, from wherever.
+#[derive(Clone)]
+pub struct Toasts {
+    messages: Sender<String>,
+}
+
+impl Toasts {
+    pub fn show(&self, message: &str)
+    {
+        // The view owns the receiver, and lives as long as the tree.
+        let _ = self.messages.send(message.into());
+    }
+}
+
+// Shows short notifications for a while, one after the other.
+// Doesn't take any input.
+pub struct ToastView {
+    current: Option<String>,
+    until: Instant,
+    rect: Rect,
+    shown: Shown<Option<String>>,
+    handle: Toasts,
+    messages: Receiver<String>,
+}
+
+impl Default for ToastView
+{
+    fn default() -> Self {
+        let (sender, messages) = unbounded();
+        Self {
+            current: None,
+            until: Instant::now(),
+            rect: Rect::NOTHING,
+            shown: Shown::default(),
+            handle: Toasts { messages: sender },
+            messages,
+        }
+    }
+}
+
+impl ToastView {
+    pub fn handle(&self) -> Toasts
+    {
+        self.handle.clone()
+    }
+}
+
+impl View for ToastView
+{
+    fn feed(&mut self, _event: Event, _model: &mut Model) -> bool {
+        false
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, _model: &Model) -> eframe::egui::Response {
+        match &self.current {
+            Some(message) if self.active() => {
+                let rect = self.rect;
+                let response = ui.allocate_rect(rect, Sense::click());
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, Rounding::same(8.0), Color32::from_gray(60));
+                painter.text(rect.center(), Align2::CENTER_CENTER, message, layout::font(14.0, layout::scale(rect, TOAST_DESIGN)), Color32::WHITE);
+                response
+            }
+            _ => self.null_response(ui),
+        }
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = TOAST.rect(rect);
+    }
+
+    // The next message is picked up here, so it's
+    // reported before it's drawn.
+    fn damage(&mut self, _model: &Model, damage: &mut Damage) {
+        if !self.active() {
+            self.current = self.messages.try_recv().ok();
+            self.until = Instant::now() + TOAST_TIMEOUT;
+        }
+        let active = self.active();
+        if self.shown.update(self.current.clone().filter(|_| active)) {
+            damage.add(self.rect);
+        }
+        if active {
+            damage.wake_at(self.until);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+
+    fn active(&self) -> bool {
+        self.current.is_some() && self.until >= Instant::now()
+    }
+}
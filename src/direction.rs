@@ -0,0 +1,93 @@
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Vec2, Color32, Rounding, Stroke, vec2}, emath::Align2};
+
+use crate::view::View;
+use crate::layout::{self, Place, Anchor};
+use crate::damage::{Damage, Shown};
+use crate::segment::{TextFrame, Field};
+use crate::event::{Event, MAIN_ENCODER};
+use crate::model::{Direction, Model};
+
+// A box a bit above the middle of the screen, designed for 140x84.
+const OVERLAY: Place = Place::new(Anchor::Center, 0.4375, 0.42).offset(0.0, -0.1);
+const DESIGN: Vec2 = vec2(140.0, 84.0);
+
+// Selects the playback direction, with the arrows or the encoder.
+pub struct DirectionView {
+    place: Place,
+    rect: Rect,
+    shown: Shown<Direction>,
+}
+
+impl Default for DirectionView
+{
+    fn default() -> Self {
+        Self { place: OVERLAY, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+}
+
+impl DirectionView {
+    pub fn with_place(mut self, place: Place) -> Self
+    {
+        self.place = place;
+        self
+    }
+
+    fn change(&self, delta: i32, model: &mut Model)
+    {
+        let count = Direction::ALL.len() as i32;
+        let current = Direction::ALL.iter().position(|d| *d == model.direction).unwrap_or(0) as i32;
+        model.set_direction(Direction::ALL[(current + delta).rem_euclid(count) as usize]);
+    }
+}
+
+impl View for DirectionView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed(Key::ArrowLeft, event.clone()) {
+            self.change(-1, model);
+            true
+        } else if self.pressed(Key::ArrowRight, event.clone()) {
+            self.change(1, model);
+            true
+        } else if let Event::Encoder { id: MAIN_ENCODER, delta, .. } = event {
+            self.change(delta, model);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, model: &Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter_at(rect);
+            painter.rect(rect, Rounding::same(4.0), Color32::BLACK, Stroke::new(2.0_f32, Color32::WHITE));
+            let scale = layout::scale(rect, DESIGN);
+            let lines = layout::rows(rect.shrink(6.0 * scale), Direction::ALL.len(), 0.0);
+            for (direction, line) in Direction::ALL.iter().zip(lines) {
+                let color = if *direction == model.direction { Color32::RED } else { Color32::GRAY };
+                painter.text(line.center(), Align2::CENTER_CENTER, direction.name(), layout::font(14.0, scale), color);
+            }
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = self.place.rect(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.shown.update(model.direction) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        frame.set(Field::Direction, model.direction.short().into());
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::{Duration, Instant};
+
+use eframe::egui::{self, Sense};
+use eframe::epaint::{Rect, Vec2, Pos2, Color32, Rounding, Stroke};
+
+use crate::view::View;
+use crate::model::Model;
+use crate::event::Event;
+use crate::led::LedFrame;
+use crate::segment::TextFrame;
+use crate::damage::Damage;
+
+// A named render target: a screen with a view tree of its own,
+// refreshed at its own rate, and only when its views changed.
+// Between refreshes it keeps showing the model as it was, like a
+// slow display on the device would.
+pub struct Display {
+    name: String,
+    root: Box<dyn View>,
+    size: Vec2,
+    rect: Rect,
+    refresh: Duration,
+    next: Instant,
+    shown: Model,
+    // What changed since the last refresh, waiting for it to be due.
+    pending: Vec<Rect>,
+    // When the views change by themselves next.
+    wake: Option<Instant>,
+    // The regions of the last refresh, all a device would transmit.
+    damaged: Vec<Rect>,
+    show_damage: bool,
+}
+
+impl Display {
+    pub fn new(name: &str, mut root: Box<dyn View>, size: Vec2, refresh_hz: f64, model: &Model) -> Self
+    {
+        let rect = Rect::from_min_size(Pos2::ZERO, size);
+        root.layout(rect);
+        Self {
+            name: name.into(),
+            root,
+            size,
+            rect,
+            refresh: Duration::from_secs_f64(1.0 / refresh_hz),
+            next: Instant::now(),
+            shown: model.clone(),
+            pending: vec![],
+            wake: None,
+            damaged: vec![],
+            show_damage: false,
+        }
+    }
+
+    // Outline the regions of each refresh, to see what gets drawn.
+    pub fn show_damage(&mut self)
+    {
+        self.show_damage = true;
+    }
+
+    pub fn size(&self) -> Vec2
+    {
+        self.size
+    }
+
+    pub fn feed(&mut self, event: Event, model: &mut Model) -> bool
+    {
+        self.root.feed(event, model)
+    }
+
+    // Asks the views what changed. Only if something did, and the
+    // display is due, it takes a new look at the model and returns
+    // true. Otherwise the display is left alone entirely.
+    pub fn refresh(&mut self, model: &Model, now: Instant) -> bool
+    {
+        let mut damage = Damage::default();
+        self.root.damage(model, &mut damage);
+        self.pending.extend_from_slice(damage.rects());
+        self.wake = damage.wake();
+        if self.pending.is_empty() || now < self.next {
+            return false;
+        }
+        self.next = now + self.refresh;
+        self.shown = model.clone();
+        self.damaged = std::mem::take(&mut self.pending);
+        true
+    }
+
+    // When the display needs refreshing without any input, if ever:
+    // when changes wait for it to be due, or views change by themselves.
+    pub fn deadline(&self) -> Option<Instant>
+    {
+        let due = (!self.pending.is_empty()).then_some(self.next);
+        due.into_iter().chain(self.wake).min()
+    }
+
+    // Shows the screen with its name above it. The tree is laid
+    // out again whenever the screen moves in the window, and all of
+    // it is drawn anew with the next refresh.
+    pub fn ui(&mut self, ui: &mut egui::Ui)
+    {
+        ui.vertical(|ui| {
+            ui.label(&self.name);
+            let (rect, _) = ui.allocate_exact_size(self.size, Sense::hover());
+            if rect != self.rect {
+                self.rect = rect;
+                self.root.layout(rect);
+                self.root.invalidate();
+                self.pending.clear();
+                self.next = Instant::now();
+                ui.ctx().request_repaint();
+            }
+            ui.painter().rect_filled(rect, Rounding::none(), Color32::BLACK);
+            ui.allocate_ui_at_rect(rect, |ui| self.root.ui(ui, &self.shown));
+            if self.show_damage {
+                for damaged in &self.damaged {
+                    ui.painter().rect_stroke(*damaged, Rounding::none(), Stroke::new(1.0_f32, Color32::YELLOW));
+                }
+            }
+        });
+    }
+
+    pub fn leds(&self, frame: &mut LedFrame, model: &Model)
+    {
+        self.root.leds(frame, model);
+    }
+
+    pub fn text(&self, frame: &mut TextFrame, model: &Model)
+    {
+        self.root.text(frame, model);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{LayoutSpec, DEFAULT_LAYOUT};
+
+    fn main_display(model: &Model) -> Display
+    {
+        LayoutSpec::parse(DEFAULT_LAYOUT).unwrap().build(model).remove(0)
+    }
+
+    #[test]
+    fn unchanged_models_need_no_refresh()
+    {
+        let model = Model::default();
+        let mut display = main_display(&model);
+        let now = Instant::now();
+        assert!(display.refresh(&model, now));
+        assert!(!display.damaged.is_empty());
+
+        let later = now + Duration::from_secs(1);
+        assert!(!display.refresh(&model, later));
+        assert!(display.pending.is_empty());
+        assert_eq!(display.deadline(), None);
+    }
+
+    #[test]
+    fn changes_wait_for_the_display_to_be_due()
+    {
+        let mut model = Model::default();
+        let mut display = main_display(&model);
+        let now = Instant::now();
+        assert!(display.refresh(&model, now));
+        let next = display.next;
+
+        // Too early, the change is kept until the display is due.
+        model.change_bpm(1.0);
+        assert!(!display.refresh(&model, now));
+        assert!(!display.pending.is_empty());
+        assert_eq!(display.shown.bpm, 120.0);
+        assert_eq!(display.deadline(), Some(next));
+
+        // Then it's shown, even if nothing changed in between.
+        assert!(display.refresh(&model, next));
+        assert_eq!(display.shown.bpm, 121.0);
+        assert!(display.pending.is_empty());
+        assert_eq!(display.deadline(), None);
+    }
+}
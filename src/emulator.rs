@@ -0,0 +1,227 @@
+use std::{ffi::CStr, fs::File, io::Write, os::fd::{AsRawFd, FromRawFd}, sync::Mutex, time::Duration};
+
+use anyhow::bail;
+use crossbeam_channel::Receiver;
+use eframe::{egui::{self, Button, RichText, Sense}, epaint::{Color32, FontId, Pos2, Rounding, Stroke, pos2, vec2}};
+
+use crate::panel::{self, Message, BUTTONS};
+use crate::led::{LedFrame, GRID_WIDTH, GRID_HEIGHT};
+use crate::segment::{DIGITS, LCD_LINES};
+use crate::damage::Waker;
+
+const ENCODER: u8 = 0;
+const PAD_SIZE: f32 = 32.0;
+// Dragging the encoder this far is one detent.
+const DRAG_PER_DETENT: f32 = 6.0;
+
+static PTSNAME: Mutex<()> = Mutex::new(());
+
+// Opens a pseudo-terminal pair. The host connects to the returned
+// path as if it were the serial port of the real panel.
+fn open_pty() -> anyhow::Result<(File, File, String)>
+{
+    unsafe {
+        let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let master = File::from_raw_fd(fd);
+        if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // ptsname_r isn't there everywhere, and ptsname returns a
+        // static buffer, so the lock keeps other callers out.
+        let path = {
+            let _lock = PTSNAME.lock().unwrap_or_else(|err| err.into_inner());
+            let name = libc::ptsname(fd);
+            if name.is_null() {
+                bail!("can't determine the name of the pseudo-terminal");
+            }
+            CStr::from_ptr(name).to_string_lossy().into_owned()
+        };
+        // We keep the slave end open ourselves. Otherwise reading the master
+        // fails as long as no host is connected, and whenever it disconnects.
+        let slave = File::options().read(true).write(true).open(&path)?;
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(slave.as_raw_fd(), &mut termios) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        libc::cfmakeraw(&mut termios);
+        if libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok((master, slave, path))
+    }
+}
+
+// A stand-in for the hardware front panel, speaking the serial
+// protocol over a pseudo-terminal.
+pub struct PanelEmulator {
+    master: File,
+    _slave: File,
+    path: String,
+    messages: Receiver<Message>,
+    leds: [Color32; BUTTONS.len()],
+    pads: LedFrame,
+    digits: [u8; DIGITS],
+    lcd: [String; LCD_LINES],
+    down: [bool; BUTTONS.len()],
+    drag: f32,
+}
+
+impl PanelEmulator {
+    pub fn new() -> anyhow::Result<Self>
+    {
+        let (master, slave, path) = open_pty()?;
+        // Nobody to wake, the emulator checks for messages regularly anyway.
+        let messages = panel::spawn_reader(master.try_clone()?, Waker::default());
+        println!("Panel emulator listening on {}", path);
+        Ok(Self {
+            master,
+            _slave: slave,
+            path,
+            messages,
+            leds: [Color32::from_gray(60); BUTTONS.len()],
+            pads: LedFrame::default(),
+            digits: [0; DIGITS],
+            lcd: Default::default(),
+            down: [false; BUTTONS.len()],
+            drag: 0.0,
+        })
+    }
+
+    fn send(&self, message: Message)
+    {
+        if let Err(err) = (&self.master).write_all(&message.encode()) {
+            eprintln!("emulator: write failed: {}", err);
+        }
+    }
+
+    fn receive(&mut self)
+    {
+        for message in self.messages.try_iter() {
+            match message {
+                Message::Led { id, color } => {
+                    if let Some(led) = self.leds.get_mut(id as usize) {
+                        *led = color;
+                    }
+                }
+                Message::Pad { x, y, color } => self.pads.set(x as usize, y as usize, color),
+                Message::Segments { digits } => self.digits = digits,
+                Message::Lcd { line, text } => {
+                    if let Some(lcd) = self.lcd.get_mut(line as usize) {
+                        *lcd = String::from_utf8_lossy(&text).into_owned();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn seven_segment(&self, ui: &mut egui::Ui)
+    {
+        let (rect, _) = ui.allocate_exact_size(vec2(DIGITS as f32 * 24.0 + 8.0, 44.0), Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::same(2.0), Color32::BLACK);
+        let lit = Stroke::new(3.0_f32, Color32::RED);
+        let unlit = Stroke::new(3.0_f32, Color32::from_rgb(40, 0, 0));
+        for (i, bits) in self.digits.iter().enumerate() {
+            let o = rect.left_top() + vec2(8.0 + i as f32 * 24.0, 4.0);
+            let p = |x: f32, y: f32| -> Pos2 { o + vec2(x, y) };
+            // Segments a to g, as lines.
+            let segments = [
+                [p(2.0, 0.0), p(14.0, 0.0)],
+                [p(16.0, 2.0), p(16.0, 16.0)],
+                [p(16.0, 20.0), p(16.0, 34.0)],
+                [p(2.0, 36.0), p(14.0, 36.0)],
+                [p(0.0, 20.0), p(0.0, 34.0)],
+                [p(0.0, 2.0), p(0.0, 16.0)],
+                [p(2.0, 18.0), p(14.0, 18.0)],
+            ];
+            for (bit, segment) in segments.iter().enumerate() {
+                painter.line_segment(*segment, if bits & (1 << bit) != 0 { lit } else { unlit });
+            }
+            painter.circle_filled(p(19.0, 36.0), 1.5, if bits & 0x80 != 0 { lit.color } else { unlit.color });
+        }
+    }
+
+    fn character_lcd(&self, ui: &mut egui::Ui)
+    {
+        let (rect, _) = ui.allocate_exact_size(vec2(170.0, 44.0), Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::same(2.0), Color32::from_rgb(30, 60, 200));
+        for (i, line) in self.lcd.iter().enumerate() {
+            painter.text(pos2(rect.left() + 6.0, rect.top() + 6.0 + i as f32 * 18.0), egui::Align2::LEFT_TOP, line, FontId::monospace(14.0), Color32::WHITE);
+        }
+    }
+
+    // Turned by dragging sideways, the faster the drag, the
+    // faster the detents arrive.
+    fn encoder(&mut self, ui: &mut egui::Ui)
+    {
+        let (rect, response) = ui.allocate_exact_size(vec2(PAD_SIZE * 2.0, PAD_SIZE), Sense::drag());
+        ui.painter().rect_filled(rect, Rounding::same(PAD_SIZE / 2.0), Color32::from_gray(80));
+        ui.painter().text(rect.center(), egui::Align2::CENTER_CENTER, "ENC", FontId::proportional(14.0), Color32::WHITE);
+        self.drag += response.drag_delta().x;
+        let detents = (self.drag / DRAG_PER_DETENT).trunc();
+        if detents != 0.0 {
+            self.drag -= detents * DRAG_PER_DETENT;
+            self.send(Message::Encoder { id: ENCODER, delta: detents.clamp(-127.0, 127.0) as i8 });
+        }
+    }
+
+    // A button that reports press and release, like a physical one.
+    fn button(&mut self, ui: &mut egui::Ui, id: usize, color: Color32)
+    {
+        let text = RichText::new(BUTTONS[id].1).color(Color32::WHITE).strong();
+        let response = ui.add(Button::new(text).fill(color).min_size(vec2(PAD_SIZE, PAD_SIZE)));
+        let down = response.is_pointer_button_down_on();
+        if down != self.down[id] {
+            self.down[id] = down;
+            self.send(Message::Button { id: id as u8, pressed: down });
+        }
+    }
+}
+
+impl eframe::App for PanelEmulator {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.receive();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Panel");
+            ui.label(&self.path);
+            ui.horizontal(|ui| {
+                self.seven_segment(ui);
+                self.character_lcd(ui);
+            });
+            // The top row of the pad grid are the step buttons.
+            ui.horizontal(|ui| {
+                for x in 0..GRID_WIDTH {
+                    self.button(ui, x, self.pads.get(x, 0));
+                }
+            });
+            for y in 1..GRID_HEIGHT {
+                ui.horizontal(|ui| {
+                    for x in 0..GRID_WIDTH {
+                        let (rect, _) = ui.allocate_exact_size(vec2(PAD_SIZE, PAD_SIZE), Sense::hover());
+                        ui.painter().rect_filled(rect, Rounding::same(2.0), self.pads.get(x, y));
+                    }
+                });
+            }
+            ui.horizontal_wrapped(|ui| {
+                for id in GRID_WIDTH..BUTTONS.len() {
+                    self.button(ui, id, self.leds[id]);
+                }
+                ui.separator();
+                if ui.button("-").clicked() {
+                    self.send(Message::Encoder { id: ENCODER, delta: -1 });
+                }
+                self.encoder(ui);
+                if ui.button("+").clicked() {
+                    self.send(Message::Encoder { id: ENCODER, delta: 1 });
+                }
+            });
+        });
+        // LED updates arrive without any UI interaction.
+        ctx.request_repaint_after(Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,218 @@
+use std::{collections::HashMap, time::Instant};
+
+use eframe::egui::{self, Key};
+
+use crate::panel::{self, Control, Message};
+use crate::gesture::{Gesture, GestureDetector};
+
+// How far the mouse wheel has to scroll for one encoder detent.
+const SCROLL_PER_DETENT: f32 = 50.0;
+// Detents further apart than this count as a slow turn.
+const SLOW_TURN_SECS: f64 = 0.25;
+
+// The encoder driven by the mouse wheel.
+pub const MAIN_ENCODER: u8 = 0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Modifiers {
+    // Encoders move in small steps, without acceleration.
+    pub fine: bool,
+    // Controls perform their secondary function.
+    pub shift: bool,
+}
+
+// The events the view hierarchy is fed with. They are
+// created from keyboard and mouse, or the hardware panel.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    // Held keys repeat their press, flagged as repeat.
+    Key { key: Key, pressed: bool, repeat: bool, modifiers: Modifiers },
+    // A relative encoder turned by delta detents, at a speed of
+    // velocity detents per second.
+    Encoder { id: u8, delta: i32, velocity: f64, modifiers: Modifiers },
+    Gesture { gesture: Gesture, modifiers: Modifiers },
+    // The modifiers changed. Modifier keys themselves don't
+    // produce key events.
+    Modifiers(Modifiers),
+    // Leave whatever is in front, the same from everywhere.
+    Back,
+}
+
+impl Event {
+    pub fn modifiers(&self) -> Modifiers
+    {
+        match self {
+            Event::Key { modifiers, .. } => *modifiers,
+            Event::Encoder { modifiers, .. } => *modifiers,
+            Event::Gesture { modifiers, .. } => *modifiers,
+            Event::Modifiers(modifiers) => *modifiers,
+            Event::Back => Modifiers::default(),
+        }
+    }
+}
+
+// Turns raw input into events, keeping track of what's
+// necessary for that between events.
+#[derive(Default)]
+pub struct Input {
+    scroll: f32,
+    last_detent: HashMap<u8, Instant>,
+    panel_fine: bool,
+    panel_shift: bool,
+    last_modifiers: Modifiers,
+    gestures: GestureDetector,
+}
+
+impl Input {
+    fn modifiers(&self, keyboard: &egui::Modifiers) -> Modifiers
+    {
+        Modifiers {
+            fine: keyboard.alt || self.panel_fine,
+            shift: keyboard.shift || self.panel_shift,
+        }
+    }
+
+    // The modifiers as last reported to the views.
+    pub fn current_modifiers(&self) -> Modifiers
+    {
+        self.last_modifiers
+    }
+
+    fn modifiers_changed(&mut self, keyboard: &egui::Modifiers) -> Vec<Event>
+    {
+        let modifiers = self.modifiers(keyboard);
+        if modifiers == self.last_modifiers {
+            return vec![];
+        }
+        self.last_modifiers = modifiers;
+        vec![Event::Modifiers(modifiers)]
+    }
+
+    fn encoder(&mut self, id: u8, delta: i32, modifiers: Modifiers) -> Event
+    {
+        let now = Instant::now();
+        let velocity = match self.last_detent.insert(id, now) {
+            Some(last) => {
+                let interval = (now - last).as_secs_f64();
+                if interval < SLOW_TURN_SECS {
+                    delta.abs() as f64 / interval.max(0.001)
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0,
+        };
+        Event::Encoder { id, delta, velocity, modifiers }
+    }
+
+    // A key event, plus the gesture it completes. A chord comes
+    // before the key press completing it, so views can react to the
+    // chord before the press does anything else.
+    fn key(&mut self, key: Key, pressed: bool, repeat: bool, modifiers: Modifiers) -> Vec<Event>
+    {
+        let event = Event::Key { key, pressed, repeat, modifiers };
+        if repeat {
+            return vec![event];
+        }
+        match self.gestures.key(key, pressed, Instant::now()) {
+            Some(gesture @ Gesture::Chord(_)) => vec![Event::Gesture { gesture, modifiers }, event],
+            Some(gesture) => vec![event, Event::Gesture { gesture, modifiers }],
+            None => vec![event],
+        }
+    }
+
+    pub fn translate_egui(&mut self, event: &egui::Event, keyboard: &egui::Modifiers) -> Vec<Event>
+    {
+        let modifiers = self.modifiers(keyboard);
+        match event {
+            egui::Event::Key { key: Key::Escape, pressed, repeat, .. } => if *pressed && !*repeat { vec![Event::Back] } else { vec![] },
+            egui::Event::Key { key, pressed, repeat, .. } => self.key(*key, *pressed, *repeat, modifiers),
+            egui::Event::Scroll(delta) => {
+                self.scroll += delta.y;
+                let detents = (self.scroll / SCROLL_PER_DETENT).trunc();
+                if detents == 0.0 {
+                    return vec![];
+                }
+                self.scroll -= detents * SCROLL_PER_DETENT;
+                vec![self.encoder(MAIN_ENCODER, detents as i32, modifiers)]
+            }
+            _ => vec![],
+        }
+    }
+
+    pub fn translate_panel(&mut self, message: Message, keyboard: &egui::Modifiers) -> Vec<Event>
+    {
+        let modifiers = self.modifiers(keyboard);
+        match message {
+            Message::Button { id, pressed } => match panel::BUTTONS.get(id as usize).map(|button| button.0) {
+                // The panel doesn't repeat its buttons.
+                Some(Control::Key(key)) => self.key(key, pressed, false, modifiers),
+                Some(Control::Fine) => {
+                    self.panel_fine = pressed;
+                    self.modifiers_changed(keyboard)
+                }
+                Some(Control::Shift) => {
+                    self.panel_shift = pressed;
+                    self.modifiers_changed(keyboard)
+                }
+                Some(Control::Back) => if pressed { vec![Event::Back] } else { vec![] },
+                None => vec![],
+            },
+            Message::Encoder { id, delta } => vec![self.encoder(id, delta as i32, modifiers)],
+            _ => vec![],
+        }
+    }
+
+    // When tick has something to report just by time passing.
+    pub fn deadline(&self) -> Option<Instant>
+    {
+        self.gestures.deadline()
+    }
+
+    // Modifier changes of the keyboard, and gestures that
+    // happened just by time passing.
+    pub fn tick(&mut self, keyboard: &egui::Modifiers) -> Vec<Event>
+    {
+        let modifiers = self.modifiers(keyboard);
+        let mut res = self.modifiers_changed(keyboard);
+        res.extend(self.gestures.tick(Instant::now()).into_iter().map(|gesture| Event::Gesture { gesture, modifiers }));
+        res
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Curve {
+    // The step grows linearly with the speed above the threshold.
+    Linear,
+    // The step grows with the square of the speed above the threshold,
+    // so moderate turns stay precise and only fast ones jump.
+    Quadratic,
+}
+
+// Maps encoder turns to value changes.
+#[derive(Clone, Copy, Debug)]
+pub struct Acceleration {
+    pub curve: Curve,
+    pub step: f64,
+    pub fine_step: f64,
+    // Detents per second below which no acceleration happens.
+    pub threshold: f64,
+    pub gain: f64,
+    // Upper limit for the step multiplier.
+    pub max_factor: f64,
+}
+
+impl Acceleration {
+    pub fn change(&self, delta: i32, velocity: f64, modifiers: Modifiers) -> f64
+    {
+        if modifiers.fine {
+            return delta as f64 * self.fine_step;
+        }
+        let speed = (velocity - self.threshold).max(0.0);
+        let factor = match self.curve {
+            Curve::Linear => 1.0 + self.gain * speed,
+            Curve::Quadratic => 1.0 + self.gain * speed * speed,
+        };
+        (delta as f64 * factor.min(self.max_factor)).round() * self.step
+    }
+}
@@ -0,0 +1,215 @@
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Color32, Rounding, vec2}, emath::Align2};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::view::{View, ModalHandle};
+use crate::layout;
+use crate::damage::{Damage, Shown};
+use crate::event::Event;
+use crate::model::{Model, Note, Scale, NOTE_COUNT};
+
+// Spreads hits as evenly as possible over the steps, the
+// rotation moves the first hit away from the downbeat.
+pub fn euclidean(hits: usize, steps: usize, rotation: usize) -> Vec<bool>
+{
+    if steps == 0 {
+        return vec![];
+    }
+    let hits = hits.min(steps);
+    (0..steps)
+        .map(|i| ((i + steps - rotation % steps) * hits) % steps < hits)
+        .collect()
+}
+
+// Settings for generated patterns. The same settings and
+// seed always give the same pattern.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Generator
+{
+    pub hits: usize,
+    pub steps: usize,
+    pub rotation: usize,
+    pub seed: u64,
+}
+
+impl Default for Generator
+{
+    fn default() -> Self {
+        Self { hits: 3, steps: NOTE_COUNT, rotation: 0, seed: 1 }
+    }
+}
+
+impl Generator {
+    // The rhythm repeats every `steps` steps, the melody doesn't.
+    pub fn generate(&self, scale: Scale) -> [Option<Note>; NOTE_COUNT]
+    {
+        let rhythm = euclidean(self.hits, self.steps, self.rotation);
+        // StdRng may change between rand versions and platforms,
+        // a named algorithm gives the same melody for a seed anywhere.
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut notes = [None; NOTE_COUNT];
+        for (i, note) in notes.iter_mut().enumerate() {
+            if rhythm[i % rhythm.len()] {
+                *note = Some(scale.sample(&mut rng));
+            }
+        }
+        notes
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Parameter {
+    Hits,
+    Steps,
+    Rotation,
+    Seed,
+}
+
+const PARAMETERS: [Parameter; 4] = [Parameter::Hits, Parameter::Steps, Parameter::Rotation, Parameter::Seed];
+
+const HELP: [&str; 2] = [
+    "</> select  ^/v change  N new seed",
+    "S scale  G done",
+];
+
+// A sub-page to generate patterns from the settings in the
+// model. Every change regenerates the pattern right away.
+pub struct GeneratorView {
+    modals: ModalHandle,
+    selected: usize,
+    rect: Rect,
+    shown: Shown<(Generator, Scale, usize)>,
+}
+
+impl GeneratorView {
+    pub fn new(modals: ModalHandle) -> Self
+    {
+        Self { modals, selected: 0, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+
+    fn change(&self, delta: i64, model: &mut Model)
+    {
+        let mut generator = model.generator;
+        let add = |value: usize, max: usize| (value as i64 + delta).clamp(0, max as i64) as usize;
+        match PARAMETERS[self.selected] {
+            Parameter::Hits => generator.hits = add(generator.hits, generator.steps),
+            Parameter::Steps => {
+                generator.steps = add(generator.steps, NOTE_COUNT).max(1);
+                generator.hits = generator.hits.min(generator.steps);
+                generator.rotation = generator.rotation.min(generator.steps - 1);
+            }
+            Parameter::Rotation => generator.rotation = add(generator.rotation, generator.steps - 1),
+            Parameter::Seed => generator.seed = generator.seed.saturating_add_signed(delta),
+        }
+        model.set_generator(generator);
+    }
+}
+
+impl View for GeneratorView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed_once(Key::G, event.clone()) {
+            self.modals.pop();
+        } else if self.pressed(Key::ArrowLeft, event.clone()) {
+            self.selected = (self.selected + PARAMETERS.len() - 1) % PARAMETERS.len();
+        } else if self.pressed(Key::ArrowRight, event.clone()) {
+            self.selected = (self.selected + 1) % PARAMETERS.len();
+        } else if self.pressed(Key::ArrowUp, event.clone()) {
+            self.change(1, model);
+        } else if self.pressed(Key::ArrowDown, event.clone()) {
+            self.change(-1, model);
+        } else if let Event::Encoder { delta, .. } = event {
+            self.change(delta as i64, model);
+        } else if self.pressed_once(Key::N, event.clone()) {
+            model.set_generator(Generator { seed: thread_rng().gen_range(0..10000), ..model.generator });
+        } else if self.pressed_once(Key::S, event) {
+            model.next_scale();
+            model.generate();
+        }
+        // Nothing gets through to the views below while generating.
+        true
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, model: &Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::none(), Color32::from_gray(20));
+        let generator = &model.generator;
+        let values = [
+            format!("hits {}", generator.hits),
+            format!("steps {}", generator.steps),
+            format!("rot {}", generator.rotation),
+            format!("seed {}", generator.seed),
+        ];
+        let scale = layout::scale(rect, layout::PAGE_DESIGN);
+        let rows = layout::rows(rect.shrink2(vec2(8.0, 2.0) * scale), 2 + HELP.len(), 0.0);
+        painter.text(rows[0].left_center(), Align2::LEFT_CENTER, format!("GENERATE  {}", model.scale.name()), layout::font(12.0, scale), Color32::WHITE);
+        let mut pos = rows[1].left_center();
+        for (i, value) in values.iter().enumerate() {
+            let color = if i == self.selected { Color32::YELLOW } else { Color32::WHITE };
+            let text = painter.text(pos, Align2::LEFT_CENTER, value, layout::font(12.0, scale), color);
+            pos.x = text.right() + 12.0 * scale;
+        }
+        for (line, row) in HELP.iter().zip(&rows[2..]) {
+            painter.text(row.left_center(), Align2::LEFT_CENTER, line, layout::font(11.0, scale), Color32::GRAY);
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = layout::PAGE.rect(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.shown.update((model.generator, model.scale, self.selected)) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(rhythm: &[bool]) -> String
+    {
+        rhythm.iter().map(|hit| if *hit { 'x' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn euclidean_spreads_the_hits()
+    {
+        assert_eq!(pattern(&euclidean(3, 8, 0)), "x..x..x.");
+        assert_eq!(pattern(&euclidean(5, 8, 0)), "x.x.xx.x");
+        assert_eq!(pattern(&euclidean(0, 4, 0)), "....");
+        assert_eq!(pattern(&euclidean(9, 4, 0)), "xxxx");
+        assert!(euclidean(3, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn rotation_shifts_the_hits_later()
+    {
+        assert_eq!(pattern(&euclidean(3, 8, 1)), ".x..x..x");
+        assert_eq!(pattern(&euclidean(3, 8, 2)), "x.x..x..");
+        for rotation in 0..16 {
+            let mut expected = euclidean(3, 8, 0);
+            expected.rotate_right(rotation % 8);
+            assert_eq!(euclidean(3, 8, rotation), expected);
+        }
+    }
+
+    // Changing these means saved seeds give other melodies than before.
+    #[test]
+    fn seeds_give_the_same_melody()
+    {
+        let generator = Generator { hits: 5, steps: 8, rotation: 0, seed: 42 };
+        use Note::*;
+        assert_eq!(generator.generate(Scale::All), [Some(B), None, Some(E), None, Some(B), Some(G), None, Some(F)]);
+        assert_eq!(generator.generate(Scale::Pentatonic), [Some(E), None, Some(G), None, Some(C), Some(E), None, Some(C)]);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use eframe::egui::Key;
+
+const LONG_PRESS: Duration = Duration::from_millis(600);
+// Destructive actions ask for a clearly longer hold.
+const VERY_LONG_PRESS: Duration = Duration::from_millis(2000);
+const DOUBLE_TAP: Duration = Duration::from_millis(300);
+
+// Higher level interactions, derived from button presses
+// and releases over time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Gesture {
+    // Pressed and released quickly.
+    Tap(Key),
+    // The second of two taps in quick succession. The first one
+    // was reported as a tap already.
+    DoubleTap(Key),
+    // Held down for a while. Fires while still held.
+    LongPress(Key),
+    // Held on even longer, after the long press.
+    VeryLongPress(Key),
+    // Several buttons held at once, in the order they were pressed.
+    Chord(Vec<Key>),
+}
+
+struct Held {
+    key: Key,
+    since: Instant,
+    // Once part of a chord, a button doesn't produce any other
+    // gesture until released.
+    consumed: bool,
+    // The long presses reported so far.
+    presses: usize,
+}
+
+impl Held {
+    // When the next long press fires, if any is left.
+    fn next_press(&self) -> Option<Instant>
+    {
+        if self.consumed {
+            return None;
+        }
+        [LONG_PRESS, VERY_LONG_PRESS].get(self.presses).map(|after| self.since + *after)
+    }
+}
+
+#[derive(Default)]
+pub struct GestureDetector {
+    held: Vec<Held>,
+    last_tap: HashMap<Key, Instant>,
+}
+
+impl GestureDetector {
+    pub fn key(&mut self, key: Key, pressed: bool, now: Instant) -> Option<Gesture>
+    {
+        if pressed {
+            // Key repeats of a held button are of no interest.
+            if self.held.iter().any(|held| held.key == key) {
+                return None;
+            }
+            self.held.push(Held { key, since: now, consumed: false, presses: 0 });
+            if self.held.len() > 1 {
+                for held in &mut self.held {
+                    held.consumed = true;
+                }
+                return Some(Gesture::Chord(self.held.iter().map(|held| held.key).collect()));
+            }
+            None
+        } else {
+            let index = self.held.iter().position(|held| held.key == key)?;
+            let held = self.held.remove(index);
+            if held.consumed || held.presses > 0 || now - held.since >= LONG_PRESS {
+                return None;
+            }
+            match self.last_tap.remove(&key) {
+                Some(last) if now - last < DOUBLE_TAP => Some(Gesture::DoubleTap(key)),
+                _ => {
+                    self.last_tap.insert(key, now);
+                    Some(Gesture::Tap(key))
+                }
+            }
+        }
+    }
+
+    // When the next long press fires, unless the button
+    // is released before.
+    pub fn deadline(&self) -> Option<Instant>
+    {
+        self.held.iter().filter_map(Held::next_press).min()
+    }
+
+    // Needs to be called regularly, long presses happen
+    // without any further input.
+    pub fn tick(&mut self, now: Instant) -> Vec<Gesture>
+    {
+        let mut res = vec![];
+        for held in &mut self.held {
+            while held.next_press().is_some_and(|at| now >= at) {
+                res.push(match held.presses {
+                    0 => Gesture::LongPress(held.key),
+                    _ => Gesture::VeryLongPress(held.key),
+                });
+                held.presses += 1;
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration
+    {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn quick_presses_are_taps_and_double_taps()
+    {
+        let mut gestures = GestureDetector::default();
+        let start = Instant::now();
+        assert_eq!(gestures.key(Key::A, true, start), None);
+        assert_eq!(gestures.key(Key::A, false, start + ms(100)), Some(Gesture::Tap(Key::A)));
+        gestures.key(Key::A, true, start + ms(200));
+        assert_eq!(gestures.key(Key::A, false, start + ms(250)), Some(Gesture::DoubleTap(Key::A)));
+        // A third tap starts over, and so does one after a pause.
+        gestures.key(Key::A, true, start + ms(300));
+        assert_eq!(gestures.key(Key::A, false, start + ms(350)), Some(Gesture::Tap(Key::A)));
+        gestures.key(Key::A, true, start + ms(1000));
+        assert_eq!(gestures.key(Key::A, false, start + ms(1050)), Some(Gesture::Tap(Key::A)));
+    }
+
+    #[test]
+    fn holding_fires_long_presses_and_no_tap()
+    {
+        let mut gestures = GestureDetector::default();
+        let start = Instant::now();
+        gestures.key(Key::A, true, start);
+        assert_eq!(gestures.deadline(), Some(start + LONG_PRESS));
+        assert!(gestures.tick(start + ms(500)).is_empty());
+        assert_eq!(gestures.tick(start + LONG_PRESS), vec![Gesture::LongPress(Key::A)]);
+        assert_eq!(gestures.deadline(), Some(start + VERY_LONG_PRESS));
+        // Repeats of the held key change nothing.
+        assert_eq!(gestures.key(Key::A, true, start + ms(1000)), None);
+        assert!(gestures.tick(start + ms(1000)).is_empty());
+        assert_eq!(gestures.tick(start + VERY_LONG_PRESS), vec![Gesture::VeryLongPress(Key::A)]);
+        assert_eq!(gestures.deadline(), None);
+        assert_eq!(gestures.key(Key::A, false, start + ms(2500)), None);
+    }
+
+    #[test]
+    fn ticking_late_reports_both_long_presses()
+    {
+        let mut gestures = GestureDetector::default();
+        let start = Instant::now();
+        gestures.key(Key::A, true, start);
+        assert_eq!(gestures.tick(start + ms(3000)), vec![Gesture::LongPress(Key::A), Gesture::VeryLongPress(Key::A)]);
+    }
+
+    #[test]
+    fn holding_several_keys_is_a_chord()
+    {
+        let mut gestures = GestureDetector::default();
+        let start = Instant::now();
+        gestures.key(Key::Num1, true, start);
+        assert_eq!(gestures.key(Key::Num3, true, start + ms(100)), Some(Gesture::Chord(vec![Key::Num1, Key::Num3])));
+        // The keys of a chord neither tap nor long press.
+        assert_eq!(gestures.deadline(), None);
+        assert!(gestures.tick(start + ms(3000)).is_empty());
+        assert_eq!(gestures.key(Key::Num3, false, start + ms(3100)), None);
+        assert_eq!(gestures.key(Key::Num1, false, start + ms(3200)), None);
+    }
+}
@@ -0,0 +1,90 @@
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Vec2, Color32, Rounding, vec2}, emath::Align2};
+
+use crate::view::{View, ModalHandle};
+use crate::layout;
+use crate::damage::{Damage, Shown};
+use crate::event::Event;
+
+// The keys of the main display, the ones of the base views come
+// from the layout, see `LayoutSpec::bindings`.
+const BINDINGS: [(&str, &str); 10] = [
+    ("1-8", "hold: select note, long: clear"),
+    ("1-8 + 1-8", "copy step"),
+    ("</>", "BPM, shift x10, alt 0.1"),
+    ("wheel", "BPM / volume, alt: fine"),
+    ("shift 1-8", "mute step"),
+    ("P", "play/pause, shift: restart"),
+    ("S / K", "stop / count-in"),
+    ("C / R", "clear pattern / tempo ramp"),
+    ("E / G", "pattern operations / generate"),
+    ("H / Esc", "this help / back"),
+];
+
+// As many rows as the original list fit at full size.
+const FULL_ROWS: usize = 14;
+
+// Fills the whole screen, designed for 320x200.
+const DESIGN: Vec2 = vec2(320.0, 200.0);
+
+// A sub-page listing the controls. Lives on the modal stack,
+// and is left with Back, or H again.
+pub struct HelpView {
+    modals: ModalHandle,
+    // Key and function of the base views.
+    bindings: Vec<(String, String)>,
+    rect: Rect,
+    shown: Shown<()>,
+}
+
+impl HelpView {
+    pub fn new(modals: ModalHandle, bindings: Vec<(String, String)>) -> Self
+    {
+        Self { modals, bindings, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+}
+
+impl View for HelpView
+{
+    fn feed(&mut self, event: Event, _model: &mut crate::model::Model) -> bool {
+        if self.pressed_once(Key::H, event) {
+            self.modals.pop();
+            return true;
+        }
+        false
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, _model: &crate::model::Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::none(), Color32::from_black_alpha(230));
+        let scale = layout::scale(rect, DESIGN);
+        let bindings: Vec<(&str, &str)> = BINDINGS.iter().copied()
+            .chain(self.bindings.iter().map(|(control, function)| (control.as_str(), function.as_str())))
+            .collect();
+        let rows = layout::rows(rect.shrink2(vec2(8.0, 5.0) * scale), bindings.len(), 0.0);
+        // Longer layouts get smaller print rather than overlapping rows.
+        let size = 12.0 * (FULL_ROWS as f32 / bindings.len() as f32).min(1.0);
+        for ((control, function), row) in bindings.into_iter().zip(rows) {
+            let pos = row.left_center();
+            painter.text(pos, Align2::LEFT_CENTER, control, layout::font(size, scale), Color32::WHITE);
+            painter.text(pos + vec2(88.0 * scale, 0.0), Align2::LEFT_CENTER, function, layout::font(size, scale), Color32::GRAY);
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    // Nothing changes on here, once drawn it stays.
+    fn damage(&mut self, _model: &crate::model::Model, damage: &mut Damage) {
+        if self.shown.update(()) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+}
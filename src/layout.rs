@@ -0,0 +1,130 @@
+use eframe::emath::Align2;
+use eframe::epaint::{Rect, Vec2, FontId, vec2};
+use serde::Deserialize;
+
+// Text below this gets unreadable, even on a small OLED.
+const MIN_FONT_SIZE: f32 = 6.0;
+
+// Sub-pages take the lower part of the screen, below the steps.
+// They were designed for 320x76.
+pub const PAGE: Place = Place::new(Anchor::Bottom, 1.0, 0.38);
+pub const PAGE_DESIGN: Vec2 = vec2(320.0, 76.0);
+
+// Where a placed rect sits within its parent.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    fn align(self) -> Align2
+    {
+        match self {
+            Anchor::TopLeft => Align2::LEFT_TOP,
+            Anchor::Top => Align2::CENTER_TOP,
+            Anchor::TopRight => Align2::RIGHT_TOP,
+            Anchor::Left => Align2::LEFT_CENTER,
+            Anchor::Center => Align2::CENTER_CENTER,
+            Anchor::Right => Align2::RIGHT_CENTER,
+            Anchor::BottomLeft => Align2::LEFT_BOTTOM,
+            Anchor::Bottom => Align2::CENTER_BOTTOM,
+            Anchor::BottomRight => Align2::RIGHT_BOTTOM,
+        }
+    }
+}
+
+// Places a rect relative to its parent, so it follows the parent
+// around different screen sizes. Size and offset are fractions of
+// the parent, the padding is in points and shrinks the parent first.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct Place {
+    anchor: Anchor,
+    size: [f32; 2],
+    offset: [f32; 2],
+    padding: f32,
+}
+
+impl Default for Place
+{
+    fn default() -> Self {
+        Self::new(Anchor::Center, 1.0, 1.0)
+    }
+}
+
+impl Place {
+    pub const fn new(anchor: Anchor, width: f32, height: f32) -> Self
+    {
+        Self { anchor, size: [width, height], offset: [0.0, 0.0], padding: 0.0 }
+    }
+
+    pub const fn offset(mut self, x: f32, y: f32) -> Self
+    {
+        self.offset = [x, y];
+        self
+    }
+
+    pub fn rect(&self, parent: Rect) -> Rect
+    {
+        let parent = parent.shrink(self.padding);
+        let size = parent.size() * vec2(self.size[0], self.size[1]);
+        let offset = parent.size() * vec2(self.offset[0], self.offset[1]);
+        self.anchor.align().align_size_within_rect(size, parent).translate(offset)
+    }
+}
+
+// Splits a rect into rows of equal height, top to bottom.
+pub fn rows(rect: Rect, count: usize, gap: f32) -> Vec<Rect>
+{
+    let height = (rect.height() - gap * (count as f32 - 1.0)) / count as f32;
+    (0..count).map(|i| Rect::from_min_size(
+        rect.left_top() + vec2(0.0, i as f32 * (height + gap)),
+        vec2(rect.width(), height))).collect()
+}
+
+// Splits a rect into columns of equal width, left to right.
+pub fn columns(rect: Rect, count: usize, gap: f32) -> Vec<Rect>
+{
+    let width = (rect.width() - gap * (count as f32 - 1.0)) / count as f32;
+    (0..count).map(|i| Rect::from_min_size(
+        rect.left_top() + vec2(i as f32 * (width + gap), 0.0),
+        vec2(width, rect.height()))).collect()
+}
+
+// Splits a rect into cells, row by row.
+pub fn grid(rect: Rect, columns_count: usize, rows_count: usize, gap: Vec2) -> Vec<Rect>
+{
+    rows(rect, rows_count, gap.y).into_iter()
+        .flat_map(|row| columns(row, columns_count, gap.x))
+        .collect()
+}
+
+// The largest square at the top of a rect, pads stay square
+// whatever the cell they are in.
+pub fn square(rect: Rect) -> Rect
+{
+    let side = rect.width().min(rect.height());
+    Align2::CENTER_TOP.align_size_within_rect(Vec2::splat(side), rect)
+}
+
+// How much a rect is scaled relative to the size it was designed
+// for, to scale fonts and distances along with it.
+pub fn scale(rect: Rect, design: Vec2) -> f32
+{
+    (rect.width() / design.x).min(rect.height() / design.y)
+}
+
+pub fn font(size: f32, scale: f32) -> FontId
+{
+    FontId::monospace((size * scale).max(MIN_FONT_SIZE))
+}
@@ -0,0 +1,50 @@
+use eframe::epaint::Color32;
+
+use crate::model::NOTE_COUNT;
+
+// The pad grid of the controller: one column per step, and
+// enough rows to show all note choices of a step.
+pub const GRID_WIDTH: usize = NOTE_COUNT;
+pub const GRID_HEIGHT: usize = 8;
+
+// The state of all pad LEDs. Views paint into it, similar to
+// how they paint pixels in `View::ui`. Row 0 is the top row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LedFrame {
+    pads: [[Color32; GRID_WIDTH]; GRID_HEIGHT],
+}
+
+impl Default for LedFrame
+{
+    fn default() -> Self {
+        Self { pads: [[Color32::BLACK; GRID_WIDTH]; GRID_HEIGHT] }
+    }
+}
+
+impl LedFrame {
+    pub fn set(&mut self, x: usize, y: usize, color: Color32)
+    {
+        if x < GRID_WIDTH && y < GRID_HEIGHT {
+            self.pads[y][x] = color;
+        }
+    }
+
+    // Only the emulator shows single pads, and it's Unix only.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub fn get(&self, x: usize, y: usize) -> Color32
+    {
+        self.pads[y][x]
+    }
+
+    // All pads, as (x, y, color).
+    pub fn iter(&self) -> impl Iterator<Item=(usize, usize, Color32)> + '_
+    {
+        (0..GRID_HEIGHT).flat_map(move |y| (0..GRID_WIDTH).map(move |x| (x, y, self.pads[y][x])))
+    }
+
+    // All pads that differ from the previous frame.
+    pub fn changes<'a>(&'a self, previous: &'a LedFrame) -> impl Iterator<Item=(usize, usize, Color32)> + 'a
+    {
+        self.iter().filter(|(x, y, color)| previous.pads[*y][*x] != *color)
+    }
+}
@@ -1,75 +1,307 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
-#![feature(let_chains)]
 
 mod model;
 mod view;
 mod matrix;
 mod root;
 mod volume;
+mod direction;
+mod tap;
+mod metronome;
+mod ramp;
+mod panel;
+mod led;
+mod segment;
+mod event;
+mod gesture;
+mod help;
+mod dialog;
+mod pattern;
+mod generator;
+#[cfg(unix)]
+mod emulator;
+mod audio;
+mod midi;
+mod sequencer;
+mod observer;
+mod spec;
+mod layout;
+mod display;
+mod status;
+mod damage;
 
-use eframe::egui;
-use model::Model;
-use std::cell::RefCell;
-use std::rc::Rc;
 use std::time::Instant;
 
-use view::View;
-use root::Root;
+use anyhow::anyhow;
+use crossbeam_channel::Receiver;
+use clap::{Arg, ArgAction, Command};
+use eframe::egui;
+use eframe::epaint::Color32;
+use model::{Model, TransportState, Change};
+use panel::Panel;
+use led::LedFrame;
+use segment::TextFrame;
+use event::{Input, Event};
+
+use display::Display;
+use damage::Waker;
+#[cfg(unix)]
+use emulator::PanelEmulator;
+use audio::AudioEngine;
+use midi::{Midi, MidiMessage};
+use sequencer::Sequencer;
+use observer::Observers;
+use spec::{LayoutSpec, DEFAULT_LAYOUT};
 
 
-fn main() -> Result<(), eframe::Error> {
+fn main() -> anyhow::Result<()> {
+    let command = Command::new("synth-ui")
+        .about("A small device UI framework demonstration")
+        .arg(Arg::new("panel")
+             .long("panel")
+             .value_name("DEVICE")
+             .help("Serial device of the hardware front panel"))
+        .arg(Arg::new("audio")
+             .long("audio")
+             .value_name("FILE")
+             .help("File or FIFO to write the audio to, as 16 bit 44.1kHz mono PCM"))
+        .arg(Arg::new("midi")
+             .long("midi")
+             .value_name("DEVICE")
+             .help("Raw MIDI device to take start, continue and stop from"))
+        .arg(Arg::new("layout")
+             .long("layout")
+             .value_name("FILE")
+             .help("TOML file describing the displays, their views, keys and placement"))
+        .arg(Arg::new("show-damage")
+             .long("show-damage")
+             .action(ArgAction::SetTrue)
+             .help("Outline the regions of the displays that get drawn anew"));
+    // The emulator needs a pseudo-terminal, so it's there on Unix only.
+    #[cfg(unix)]
+    let command = command
+        .subcommand(Command::new("emulator")
+                    .about("Emulate the hardware front panel on a pseudo-terminal"));
+    let matches = command.get_matches();
+
+    #[cfg(unix)]
+    if matches.subcommand_matches("emulator").is_some() {
+        let emulator = PanelEmulator::new()?;
+        let options = eframe::NativeOptions {
+            initial_window_size: Some(egui::vec2(560.0, 480.0)),
+            ..Default::default()
+        };
+        return eframe::run_native(
+            "Synth UI Panel",
+            options,
+            Box::new(|_cc| Box::new(emulator)),
+        ).map_err(|err| anyhow!("{}", err));
+    }
+
+    let layout = match matches.get_one::<String>("layout") {
+        Some(path) => LayoutSpec::load(path)?,
+        None => LayoutSpec::parse(DEFAULT_LAYOUT)?,
+    };
+    let model = Model::default();
+    let waker = Waker::default();
+    let audio = match matches.get_one::<String>("audio") {
+        Some(path) => Some(AudioEngine::open(path)?),
+        None => None,
+    };
+    let midi = match matches.get_one::<String>("midi") {
+        Some(path) => Some(Midi::open(path, waker.clone())?),
+        None => None,
+    };
+    let midi_output = match &midi {
+        Some(midi) => Some(midi.output()?),
+        None => None,
+    };
+    let mut observers = Observers::default();
+    let sequencer = Sequencer::spawn(&model, observers.subscribe(), audio, midi_output, waker.clone());
+    let mut displays = layout.build(&model);
+    if matches.get_flag("show-damage") {
+        for display in &mut displays {
+            display.show_damage();
+        }
+    }
+    // The displays side by side, with room for the heading and their names.
+    let width = displays.iter().map(|display| display.size().x).sum::<f32>()
+        + DISPLAY_SPACING * (displays.len() - 1) as f32;
+    let height = displays.iter().map(|display| display.size().y).fold(0.0, f32::max);
+    let mut app = SynthUI::new(model, displays, observers, sequencer);
+    app.midi = midi;
+    if let Some(path) = matches.get_one::<String>("panel") {
+        app.panel = Some(Panel::open(path, waker.clone())?);
+        app.panel_changes = Some(app.observers.subscribe());
+    }
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(320.0, 320.0)),
+        initial_window_size: Some(egui::vec2(width + 16.0, height + 72.0)),
         ..Default::default()
     };
     eframe::run_native(
         "Synth UI",
         options,
-        Box::new(|_cc| Box::new(SynthUI::default())),
-    )
+        Box::new(move |cc| {
+            waker.connect(&cc.egui_ctx);
+            Box::new(app)
+        }),
+    ).map_err(|err| anyhow!("{}", err))
 }
 
 
 
 
+// The space between the displays in the window.
+const DISPLAY_SPACING: f32 = 16.0;
+
+// The main display gets events first, whatever it doesn't
+// use goes on to the others.
+fn feed(displays: &mut [Display], event: Event, model: &mut Model)
+{
+    for display in displays {
+        if display.feed(event.clone(), model) {
+            return;
+        }
+    }
+}
+
 struct SynthUI {
     model: Model,
-    when: Instant,
-    root: Rc<RefCell<dyn View>>,
+    displays: Vec<Display>,
+    panel: Option<Panel>,
+    // The panel is an output like any other, and subscribes.
+    panel_changes: Option<Receiver<Change>>,
+    midi: Option<Midi>,
+    observers: Observers,
+    sequencer: Sequencer,
+    input: Input,
 }
 
-impl Default for SynthUI {
-    fn default() -> Self {
+impl SynthUI {
+    fn new(model: Model, displays: Vec<Display>, observers: Observers, sequencer: Sequencer) -> Self
+    {
         Self {
-            model: Model::default(),
-            when: Instant::now(),
-            root: Rc::new(RefCell::new(Root::default())),
+            model,
+            displays,
+            panel: None,
+            panel_changes: None,
+            midi: None,
+            observers,
+            sequencer,
+            input: Input::default(),
         }
     }
-}
-
-impl SynthUI {
 
     fn dispatch_input_keys(&mut self, ctx: &egui::Context)
     {
         ctx.input(|i| {
             for event in &i.events {
-                self.root.borrow_mut().feed(event.clone(), &mut self.model);
+                for event in self.input.translate_egui(event, &i.modifiers) {
+                    feed(&mut self.displays, event, &mut self.model);
+                }
+            }
+            for event in self.input.tick(&i.modifiers) {
+                feed(&mut self.displays, event, &mut self.model);
             }
         });
     }
+
+    fn dispatch_panel(&mut self, ctx: &egui::Context)
+    {
+        if let Some(panel) = &self.panel {
+            let modifiers = ctx.input(|i| i.modifiers);
+            for message in panel.messages() {
+                for event in self.input.translate_panel(message, &modifiers) {
+                    feed(&mut self.displays, event, &mut self.model);
+                }
+            }
+        }
+    }
+
+    // MIDI controls the transport directly, there's nothing
+    // for the views in there.
+    fn dispatch_midi(&mut self)
+    {
+        if let Some(midi) = &self.midi {
+            for message in midi.messages() {
+                match message {
+                    MidiMessage::Start => self.model.play(),
+                    MidiMessage::Continue => self.model.resume(),
+                    MidiMessage::Stop => self.model.pause(),
+                }
+            }
+        }
+    }
+
+    // The panel shows the model, the playhead, and what the views
+    // have open, so it's only brought up to date when the model
+    // changed, the playhead moved, or a display was refreshed.
+    fn update_panel(&mut self, moved: bool, refreshed: bool)
+    {
+        if let (Some(panel), Some(changes)) = (&mut self.panel, &self.panel_changes) {
+            let changed = changes.try_iter().count() > 0;
+            if !(changed || moved || refreshed) {
+                return;
+            }
+            let play = match self.model.transport {
+                TransportState::Playing => Color32::from_rgb(0, 200, 0),
+                TransportState::CountIn => Color32::from_rgb(200, 200, 0),
+                TransportState::Paused => Color32::from_rgb(0, 60, 0),
+                TransportState::Stopped => Color32::from_gray(60),
+            };
+            let mut frame = LedFrame::default();
+            for display in &self.displays {
+                display.leds(&mut frame, &self.model);
+            }
+            let mut text = TextFrame::default();
+            for display in &self.displays {
+                display.text(&mut text, &self.model);
+            }
+            let shift = match self.input.current_modifiers().shift {
+                true => Color32::WHITE,
+                false => Color32::from_gray(60),
+            };
+            let res = panel.led(panel::LED_PLAY, play)
+                .and_then(|_| panel.led(panel::LED_SHIFT, shift))
+                .and_then(|_| panel.pads(&frame))
+                .and_then(|_| panel.text(&text));
+            if let Err(err) = res {
+                eprintln!("panel: {:#}", err);
+            }
+        }
+    }
 }
 
 impl eframe::App for SynthUI {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let now = Instant::now();
-        let elapsed = now - self.when;
-        self.when = now;
-        self.model.update(elapsed);
         self.dispatch_input_keys(ctx);
+        self.dispatch_panel(ctx);
+        self.dispatch_midi();
+        self.observers.publish(&self.model.take_changes());
+        let moved = self.sequencer.sync(&mut self.model);
+        // Nothing is repainted unless a display has changes to show,
+        // some input arrives, or another thread wakes us up.
+        let now = Instant::now();
+        let mut refreshed = false;
+        for display in &mut self.displays {
+            refreshed |= display.refresh(&self.model, now);
+        }
+        self.update_panel(moved, refreshed);
+        let next = self.displays.iter()
+            .filter_map(Display::deadline)
+            .chain(self.input.deadline())
+            .min();
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Synth UI");
-            self.root.borrow_mut().ui(ui, &self.model);
+            ui.horizontal_top(|ui| {
+                ui.spacing_mut().item_spacing.x = DISPLAY_SPACING;
+                for display in &mut self.displays {
+                    display.ui(ui);
+                }
+            });
         });
+        if let Some(next) = next {
+            ctx.request_repaint_after(next.saturating_duration_since(now));
+        }
     }
 }
@@ -1,13 +1,22 @@
-use std::{cell::RefCell, rc::Rc};
+use eframe::{egui::{self, Key}, epaint::{Color32, Stroke, Rounding, Rect, pos2}};
+use std::time::Duration;
 
-use eframe::{egui::{self, Event, Key}, epaint::{Color32, Stroke, vec2, Rounding, Rect, pos2}};
-
-use crate::{view::{View, MutexViewContainer, MomentaryView}, model::{Note, NOTE_COUNT}};
+use crate::{view::{View, MutexViewContainer, MomentaryView, ToggleView}, model::{Note, NOTE_COUNT}};
 use crate::model::Model;
-use rand::Rng;
+use crate::led::LedFrame;
+use crate::event::Event;
+use crate::gesture::Gesture;
+use crate::layout::{self, Place};
+use crate::damage::{Damage, Shown};
 
 const PADDING:f32 = 4.0;
 
+// The keys selecting the steps.
+pub const STEP_KEYS: [Key; NOTE_COUNT] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8];
+
+// The choices of a note selection, top to bottom.
+const CHOICES: [Option<Note>; 8] = [Some(Note::A), Some(Note::B), Some(Note::C), Some(Note::D), Some(Note::E), Some(Note::F), Some(Note::G), None];
+
 fn note_color(note: &Option<Note>) -> Color32
 {
     match note {
@@ -26,6 +35,34 @@ fn note_color(note: &Option<Note>) -> Color32
     }
 }
 
+// Muted steps are shown dimmed, skipped ones almost off.
+fn step_color(model: &Model, step: usize) -> Color32
+{
+    let color = note_color(&model.notes[step]);
+    if model.skipped[step] {
+        color.gamma_multiply(0.1)
+    } else if model.muted[step] {
+        color.gamma_multiply(0.3)
+    } else {
+        color
+    }
+}
+
+// The square pads of the steps, side by side.
+fn pads(rect: Rect) -> Vec<Rect>
+{
+    layout::columns(rect, NOTE_COUNT, PADDING).into_iter().map(layout::square).collect()
+}
+
+// Crosses out a skipped step.
+fn cross(painter: &egui::Painter, rect: Rect)
+{
+    let stroke = Stroke::new(2.0_f32, Color32::GRAY);
+    let rect = rect.shrink(PADDING);
+    painter.line_segment([rect.left_top(), rect.right_bottom()], stroke);
+    painter.line_segment([rect.right_top(), rect.left_bottom()], stroke);
+}
+
 fn selection_for_note(note: &Option<Note>) -> usize
 {
     match note {
@@ -64,6 +101,11 @@ struct NoteSelectionView
     selection: usize,
     // Which note in the sequence
     number: usize,
+    // The key holding us open
+    key: Key,
+    // Whether the selection was changed since we opened.
+    used: bool,
+    shown: Shown<usize>,
 }
 
 impl View for NoteSelectionView
@@ -71,6 +113,7 @@ impl View for NoteSelectionView
     fn activate(&mut self, model: &Model)
     {
         self.selection = selection_for_note(&model.notes[self.number]);
+        self.used = false;
     }
 
     fn feed(&mut self, event: Event, model: &mut Model) -> bool {
@@ -80,26 +123,32 @@ impl View for NoteSelectionView
             if self.selection > 0 {
                 self.selection -= 1;
             }
+            self.used = true;
             res = true;
         } else if self.pressed(Key::ArrowRight, event.clone()) {
             self.selection = (self.selection + 1).clamp(0, 7);
+            self.used = true;
+            res = true;
+        } else if !self.used && matches!(event, Event::Gesture { gesture: Gesture::VeryLongPress(key), .. } if key == self.key) {
+            // Holding the step on, without selecting anything, clears it.
+            self.selection = selection_for_note(&None);
             res = true;
         }
-        model.notes[self.number] = note_for_selection(self.selection);
-        return res;
+        if res {
+            model.set_note(self.number, note_for_selection(self.selection));
+        }
+        res
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
+    fn ui(&mut self, ui: &mut egui::Ui, _model: &Model) -> egui::Response {
         let response = ui.allocate_rect(self.rect, egui::Sense::click());
         let rounding = Rounding::same(4.0);
         let white = Color32::from_gray(255);
-        ui.ctx().request_repaint();
         let painter = ui.painter_at(self.rect);
-        let pad_size = self.rect.width();
         painter.rect_filled(self.rect, rounding, Color32::from_gray(20));
         let mut selected = None;
-        for (i, note) in [Some(Note::A), Some(Note::B), Some(Note::C), Some(Note::D), Some(Note::E), Some(Note::F), Some(Note::G), None].iter().enumerate() {
-            let note_rect = Rect::from_min_size(self.rect.left_top() + vec2(0.0, (i as f32) * pad_size), (pad_size, pad_size).into());
+        let note_rects = layout::rows(self.rect, CHOICES.len(), 0.0);
+        for (i, (note, note_rect)) in CHOICES.iter().zip(note_rects).enumerate() {
             if self.selection == i {
                 selected = Some((note_rect, note_color(note)));
             } else {
@@ -107,39 +156,139 @@ impl View for NoteSelectionView
             }
         }
         if let Some((note_rect, color)) = selected {
-            painter.rect(note_rect, rounding, color, Stroke::new(2.0, white));
+            painter.rect(note_rect, rounding, color, Stroke::new(2.0_f32, white));
         }
 
         response
     }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    fn damage(&mut self, _model: &Model, damage: &mut Damage) {
+        if self.shown.update(self.selection) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+
+    fn leds(&self, frame: &mut LedFrame, _model: &Model) {
+        // The column of our step shows the choices, the selection in white.
+        for (i, note) in CHOICES.iter().enumerate() {
+            let color = if self.selection == i { Color32::WHITE } else { note_color(note) };
+            frame.set(self.number, i, color);
+        }
+    }
+}
+
+// While active, the steps toggle their mute state instead
+// of selecting notes, or their skip state with shift.
+struct MuteView
+{
+    rect: Rect,
+    shown: Shown<([bool; NOTE_COUNT], [bool; NOTE_COUNT])>,
 }
 
+impl View for MuteView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        for (step, key) in STEP_KEYS.iter().enumerate() {
+            if self.pressed_once(*key, event.clone()) {
+                if event.modifiers().shift {
+                    model.toggle_skip(step);
+                } else {
+                    model.toggle_mute(step);
+                }
+                return true;
+            } else if self.used(*key, event.clone()) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
+        let response = ui.allocate_rect(self.rect, egui::Sense::click());
+        let rounding = Rounding::same(4.0);
+        let painter = ui.painter_at(self.rect);
+        for (i, (muted, rect)) in model.muted.iter().zip(pads(self.rect)).enumerate() {
+            let color = if *muted { Color32::RED } else { Color32::GREEN };
+            painter.rect_stroke(rect.shrink(2.0), rounding, Stroke::new(3.0_f32, color));
+            if model.skipped[i] {
+                cross(&painter, rect);
+            }
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.shown.update((model.muted, model.skipped)) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        for (i, muted) in model.muted.iter().enumerate() {
+            let color = if model.skipped[i] {
+                Color32::from_gray(20)
+            } else if *muted {
+                Color32::from_rgb(80, 0, 0)
+            } else {
+                Color32::from_rgb(0, 200, 0)
+            };
+            frame.set(i, 0, color);
+        }
+    }
+}
+
+// The pattern and the playhead, as drawn last: notes, muted and
+// skipped steps, position, and whether a note sounds.
+type StepState = ([Option<Note>; NOTE_COUNT], [bool; NOTE_COUNT], [bool; NOTE_COUNT], usize, bool);
+
 pub struct MatrixView
 {
+    // Where the step row goes on the screen.
+    steps: Place,
     rect: Rect,
+    // A mode button: tapped it latches, held it's momentary.
+    mute_mode: ToggleView,
     note_views: MutexViewContainer,
+    shown: Shown<StepState>,
 }
 
-impl Default for MatrixView
-{
-    fn default() -> Self {
-        let rect = Rect::from_min_max((8.0, 60.0).into(), (312.0, 120.0).into());
-        let mut views: std::vec::Vec<Rc<RefCell<dyn View>>> = vec![];
-        let pad_size = (rect.width() - (PADDING * (NOTE_COUNT as f32 - 1.0))) / NOTE_COUNT as f32 ;
-        for (i, value) in [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8].iter().enumerate() {
-            let note_rect = Rect::from_min_size(
-                pos2(rect.left(), PADDING) + vec2(i as f32 * (pad_size + PADDING), 0.0),
-                vec2(pad_size, (pad_size + PADDING) * 8.0 as f32 - pad_size + PADDING)); // The 8 is 7 notes + no note
-            views.push(Rc::new(RefCell::new(
+impl MatrixView {
+    // The steps are placed on the screen, the mute key
+    // switches them to muting.
+    pub fn new(steps: Place, mute_key: Key) -> Self
+    {
+        let mut views: std::vec::Vec<Box<dyn View>> = vec![];
+        for (i, value) in STEP_KEYS.iter().enumerate() {
+            views.push(Box::new(
                 MomentaryView::new(
-                    Rc::new(RefCell::new(NoteSelectionView { rect: note_rect, selection: 0, number: i })),
+                    Box::new(NoteSelectionView { rect: Rect::NOTHING, selection: 0, number: i, key: *value, used: false, shown: Shown::default() }),
                     *value,
                 )
-            )));
+            ));
         }
         Self {
-            rect,
-            note_views: MutexViewContainer{ views }
+            steps,
+            rect: Rect::NOTHING,
+            mute_mode: ToggleView::new(Box::new(MuteView { rect: Rect::NOTHING, shown: Shown::default() }), mute_key)
+                .momentary_after(Duration::from_millis(300)),
+            note_views: MutexViewContainer{ views },
+            shown: Shown::default(),
         }
     }
 }
@@ -147,7 +296,28 @@ impl Default for MatrixView
 
 impl View for MatrixView {
     fn feed(&mut self, event: Event, model: &mut Model) -> bool {
-        self.note_views.feed(event, model)
+        if self.mute_mode.feed(event.clone(), model) {
+            return true;
+        }
+        // Shift and a step mutes it, instead of selecting its note.
+        if event.modifiers().shift {
+            if let Some(step) = STEP_KEYS.iter().position(|key| self.pressed_once(*key, event.clone())) {
+                model.toggle_mute(step);
+                return true;
+            }
+        }
+        if self.note_views.feed(event.clone(), model) {
+            return true;
+        }
+        // Holding a step and pressing another copies the first onto the second.
+        if let Event::Gesture { gesture: Gesture::Chord(keys), .. } = event {
+            let steps: Vec<_> = keys.iter().filter_map(|key| STEP_KEYS.iter().position(|k| k == key)).collect();
+            if let [from, to] = steps[..] {
+                model.copy_step(from, to);
+                return true;
+            }
+        }
+        false
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
@@ -155,25 +325,117 @@ impl View for MatrixView {
         let red = Color32::from_rgba_unmultiplied(255, 0, 0, 255);
         let rounding = Rounding::same(4.0);
 
-        let width = self.rect.width();
-        let note_count = model.notes.len() as f32;
-        let pad_size = (width - (PADDING * (note_count - 1.0))) / note_count ;
-
         if ui.is_rect_visible(self.rect) {
-            ui.ctx().request_repaint();
             let painter = ui.painter_at(self.rect);
-            let mut x = 0.0;
-            for (i, note) in model.notes.iter().enumerate() {
-                let rect = Rect::from_min_size(self.rect.left_top() + vec2(x, 2.0), vec2(pad_size, pad_size));
-                let active_color = if i == model.position { red } else { note_color(&note) };
-                if let Some(note) = *note {
-                    painter.rect_filled(rect, rounding, note_color(&Some(note)));
+            for (i, (note, rect)) in model.notes.iter().zip(pads(self.rect)).enumerate() {
+                let active_color = if i == model.position { red } else { note_color(note) };
+                if note.is_some() {
+                    painter.rect_filled(rect, rounding, step_color(model, i));
                 } else {
-                    painter.rect(rect, rounding, black, Stroke::new(2.0, active_color));
+                    painter.rect(rect, rounding, black, Stroke::new(2.0_f32, active_color));
+                }
+                if model.skipped[i] {
+                    cross(&painter, rect);
+                } else if i == model.position && model.sounding().is_some() {
+                    painter.rect_stroke(rect, rounding, Stroke::new(2.0_f32, Color32::WHITE));
                 }
-            x += pad_size + PADDING;
             }
         }
+        self.mute_mode.ui(ui, model);
         self.note_views.ui(ui, model)
     }
+
+    // The note selections open in the columns of their steps,
+    // over the whole height of the screen.
+    fn layout(&mut self, rect: Rect) {
+        self.rect = self.steps.rect(rect);
+        self.mute_mode.layout(self.rect);
+        let area = rect.shrink(PADDING);
+        let columns = layout::columns(self.rect, NOTE_COUNT, PADDING);
+        for (view, column) in self.note_views.views.iter_mut().zip(columns) {
+            view.layout(Rect::from_min_max(pos2(column.left(), area.top()), pos2(column.right(), area.bottom())));
+        }
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        let state = (model.notes, model.muted, model.skipped, model.position, model.sounding().is_some());
+        if self.shown.update(state) {
+            damage.add(self.rect);
+        }
+        self.mute_mode.damage(model, damage);
+        self.note_views.damage(model, damage);
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+        self.mute_mode.invalidate();
+        self.note_views.invalidate();
+    }
+
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        let red = Color32::from_rgba_unmultiplied(255, 0, 0, 255);
+        for i in 0..model.notes.len() {
+            frame.set(i, 0, if i == model.position { red } else { step_color(model, i) });
+        }
+        self.mute_mode.leds(frame, model);
+        self.note_views.leds(frame, model);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Modifiers;
+    use crate::layout::Anchor;
+    use crate::led::GRID_HEIGHT;
+
+    fn render(view: &MatrixView, model: &Model) -> LedFrame
+    {
+        let mut frame = LedFrame::default();
+        view.leds(&mut frame, model);
+        frame
+    }
+
+    #[test]
+    fn leds_show_pattern_playhead_and_selection()
+    {
+        let mut model = Model::default();
+        model.notes = [Some(Note::A), Some(Note::B), None, Some(Note::C), Some(Note::E), None, None, Some(Note::G)];
+        model.position = 1;
+        let mut view = MatrixView::new(Place::new(Anchor::Center, 1.0, 0.3), Key::M);
+
+        // The steps in their note colours, the playhead in red.
+        let mut expected = LedFrame::default();
+        expected.set(0, 0, Color32::from_rgb(0x9b, 0x5f, 0xe0));
+        expected.set(1, 0, Color32::RED);
+        expected.set(3, 0, Color32::from_rgb(0x60, 0xdb, 0xe8));
+        expected.set(4, 0, Color32::from_rgb(0xef, 0xdf, 0x48));
+        expected.set(7, 0, Color32::from_rgb(0xd6, 0x4e, 0x12));
+        assert_eq!(render(&view, &model), expected);
+
+        // Holding step 5 shows the choices in its column, its note E in white.
+        view.feed(Event::Key { key: Key::Num5, pressed: true, repeat: false, modifiers: Modifiers::default() }, &mut model);
+        let choices = [
+            Color32::from_rgb(0x9b, 0x5f, 0xe0),
+            Color32::from_rgb(0x16, 0xa4, 0xd8),
+            Color32::from_rgb(0x60, 0xdb, 0xe8),
+            Color32::from_rgb(0x8b, 0xd3, 0x46),
+            Color32::WHITE,
+            Color32::from_rgb(0xf9, 0xa5, 0x2c),
+            Color32::from_rgb(0xd6, 0x4e, 0x12),
+            Color32::BLACK,
+        ];
+        for (row, color) in choices.iter().enumerate() {
+            expected.set(4, row, *color);
+        }
+        assert_eq!(render(&view, &model), expected);
+
+        // Releasing it leaves the step row alone again.
+        view.feed(Event::Key { key: Key::Num5, pressed: false, repeat: false, modifiers: Modifiers::default() }, &mut model);
+        for row in 1..GRID_HEIGHT {
+            expected.set(4, row, Color32::BLACK);
+        }
+        expected.set(4, 0, Color32::from_rgb(0xef, 0xdf, 0x48));
+        assert_eq!(render(&view, &model), expected);
+    }
 }
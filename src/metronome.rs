@@ -0,0 +1,101 @@
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Vec2, Color32, Rounding, Stroke, vec2}, emath::Align2};
+
+use crate::view::View;
+use crate::layout::{self, Place, Anchor};
+use crate::damage::{Damage, Shown};
+use crate::segment::{TextFrame, Field};
+use crate::event::{Event, MAIN_ENCODER};
+use crate::model::Model;
+
+const LEVEL_STEP: f64 = 0.1;
+// A box a bit above the middle of the screen, designed for 140x70.
+const OVERLAY: Place = Place::new(Anchor::Center, 0.4375, 0.35).offset(0.0, -0.1);
+const DESIGN: Vec2 = vec2(140.0, 70.0);
+
+// Metronome settings: Enter switches the click on and off, with
+// shift the MIDI note. The arrows and the encoder set the level.
+pub struct MetronomeView {
+    place: Place,
+    rect: Rect,
+    shown: Shown<(bool, f64, bool)>,
+}
+
+impl Default for MetronomeView
+{
+    fn default() -> Self {
+        Self { place: OVERLAY, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+}
+
+impl MetronomeView {
+    pub fn with_place(mut self, place: Place) -> Self
+    {
+        self.place = place;
+        self
+    }
+}
+
+impl View for MetronomeView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed_once(Key::Enter, event.clone()) {
+            if event.modifiers().shift {
+                model.toggle_click_midi();
+            } else {
+                model.toggle_metronome();
+            }
+            true
+        } else if self.pressed(Key::ArrowLeft, event.clone()) {
+            model.change_click_level(-LEVEL_STEP);
+            true
+        } else if self.pressed(Key::ArrowRight, event.clone()) {
+            model.change_click_level(LEVEL_STEP);
+            true
+        } else if let Event::Encoder { id: MAIN_ENCODER, delta, .. } = event {
+            model.change_click_level(delta as f64 * LEVEL_STEP);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, model: &Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter_at(rect);
+            painter.rect(rect, Rounding::same(4.0), Color32::BLACK, Stroke::new(2.0_f32, Color32::WHITE));
+            let on_off = |on: bool| if on { "on" } else { "off" };
+            let lines = [
+                format!("click {}", on_off(model.metronome)),
+                format!("level {:.0}%", model.click_level * 100.0),
+                format!("MIDI {}", on_off(model.click_midi)),
+            ];
+            let scale = layout::scale(rect, DESIGN);
+            let rows = layout::rows(rect.shrink(7.0 * scale), lines.len(), 0.0);
+            for (line, row) in lines.iter().zip(rows) {
+                painter.text(row.center(), Align2::CENTER_CENTER, line, layout::font(14.0, scale), Color32::RED);
+            }
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = self.place.rect(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.shown.update((model.metronome, model.click_level, model.click_midi)) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        let text = if model.metronome { format!("{:.0}", model.click_level * 100.0) } else { "OFF".into() };
+        frame.set(Field::Click, text);
+    }
+}
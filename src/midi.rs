@@ -0,0 +1,99 @@
+use std::{fs::{File, OpenOptions}, io::{ErrorKind, Read, Write}, thread};
+
+use anyhow::Context;
+use crossbeam_channel::{unbounded, Receiver};
+
+use crate::damage::Waker;
+
+// System real-time messages, single bytes that may
+// show up anywhere in the stream.
+const START: u8 = 0xfa;
+const CONTINUE: u8 = 0xfb;
+const STOP: u8 = 0xfc;
+
+// Metronome clicks go to the drum channel (10), as side stick
+// and high wood block on the first beat.
+const NOTE_ON: u8 = 0x99;
+const NOTE_OFF: u8 = 0x89;
+const CLICK_NOTE: u8 = 37;
+const ACCENT_NOTE: u8 = 76;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiMessage {
+    Start,
+    Continue,
+    Stop,
+}
+
+// A raw MIDI device, like /dev/snd/midiC1D0, or a FIFO. Of the
+// input only transport control is of interest, anything else is
+// dropped.
+pub struct Midi {
+    device: File,
+    messages: Receiver<MidiMessage>,
+}
+
+impl Midi {
+    pub fn open(path: &str, waker: Waker) -> anyhow::Result<Self>
+    {
+        let device = OpenOptions::new().read(true).write(true).open(path)
+            .with_context(|| format!("can't open MIDI device {}", path))?;
+        let mut reader = device.try_clone()?;
+        let (sender, messages) = unbounded();
+        thread::spawn(move || {
+            let mut buffer = [0; 256];
+            loop {
+                let count = match reader.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(count) => count,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        eprintln!("midi: read failed: {}", err);
+                        break;
+                    }
+                };
+                for byte in &buffer[..count] {
+                    let message = match *byte {
+                        START => MidiMessage::Start,
+                        CONTINUE => MidiMessage::Continue,
+                        STOP => MidiMessage::Stop,
+                        _ => continue,
+                    };
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                    waker.wake();
+                }
+            }
+        });
+        Ok(Self { device, messages })
+    }
+
+    // For writing to the device from another thread.
+    pub fn output(&self) -> anyhow::Result<MidiOutput>
+    {
+        Ok(MidiOutput { device: self.device.try_clone()? })
+    }
+
+    // All messages that arrived since the last call.
+    pub fn messages(&self) -> Vec<MidiMessage>
+    {
+        self.messages.try_iter().collect()
+    }
+}
+
+pub struct MidiOutput {
+    device: File,
+}
+
+impl MidiOutput {
+    // Drum sounds don't care about the note length,
+    // so the note is ended right away.
+    pub fn click(&mut self, accent: bool, level: f64) -> anyhow::Result<()>
+    {
+        let note = if accent { ACCENT_NOTE } else { CLICK_NOTE };
+        let velocity = (level * 127.0).round() as u8;
+        self.device.write_all(&[NOTE_ON, note, velocity, NOTE_OFF, note, 0])?;
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
 use std::time::Duration;
+
+use crate::generator::Generator;
 use rand::{
     distributions::{Distribution, Standard},
     Rng,
@@ -6,8 +8,14 @@ use rand::{
 
 
 pub const NOTE_COUNT:usize = 8;
+pub const MAX_VOLUME:f64 = 11.0;
+pub const MAX_BPM:f64 = 300.0;
+// Steps are sixteenth notes.
+pub const STEPS_PER_BEAT:f64 = 4.0;
+pub const STEPS_PER_BAR:f64 = 16.0;
+pub const BEATS_PER_BAR:usize = 4;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Note {
     A,
     B,
@@ -33,20 +41,271 @@ impl Distribution<Note> for Standard {
     }
 }
 
+// Constrains which notes random patterns use.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scale
+{
+    All,
+    Pentatonic,
+    MajorTriad,
+    MinorTriad,
+}
+
+impl Scale {
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Scale::All => "all",
+            Scale::Pentatonic => "pentatonic",
+            Scale::MajorTriad => "C major",
+            Scale::MinorTriad => "A minor",
+        }
+    }
+
+    pub fn contains(&self, note: Note) -> bool
+    {
+        match self {
+            Scale::All => true,
+            Scale::Pentatonic => matches!(note, Note::A | Note::C | Note::D | Note::E | Note::G),
+            Scale::MajorTriad => matches!(note, Note::C | Note::E | Note::G),
+            Scale::MinorTriad => matches!(note, Note::A | Note::C | Note::E),
+        }
+    }
+
+    pub fn next(&self) -> Scale
+    {
+        match self {
+            Scale::All => Scale::Pentatonic,
+            Scale::Pentatonic => Scale::MajorTriad,
+            Scale::MajorTriad => Scale::MinorTriad,
+            Scale::MinorTriad => Scale::All,
+        }
+    }
+
+    // A random note of the scale.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Note
+    {
+        loop {
+            let note: Note = rng.gen();
+            if self.contains(note) {
+                return note;
+            }
+        }
+    }
+}
+
+// The order the steps are played in.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction
+{
+    Forward,
+    Reverse,
+    PingPong,
+    Random,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Forward, Direction::Reverse, Direction::PingPong, Direction::Random];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Direction::Forward => "forward",
+            Direction::Reverse => "reverse",
+            Direction::PingPong => "ping-pong",
+            Direction::Random => "random",
+        }
+    }
+
+    // Short enough for a 4 digit segment display.
+    pub fn short(&self) -> &'static str
+    {
+        match self {
+            Direction::Forward => "Fd",
+            Direction::Reverse => "rEV",
+            Direction::PingPong => "PInG",
+            Direction::Random => "rAnd",
+        }
+    }
+
+    // Which of the `len` playable steps comes at `tick`.
+    fn index(&self, tick: usize, len: usize) -> usize
+    {
+        match self {
+            Direction::Forward => tick % len,
+            Direction::Reverse => len - 1 - tick % len,
+            // The turning points aren't repeated.
+            Direction::PingPong if len > 1 => {
+                let i = tick % (2 * (len - 1));
+                if i < len { i } else { 2 * (len - 1) - i }
+            }
+            Direction::PingPong => 0,
+            Direction::Random => rand::thread_rng().gen_range(0..len),
+        }
+    }
+}
+
+// A gradual tempo change, measured in steps so it
+// takes the same musical time whatever the tempo.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ramp
+{
+    pub from: f64,
+    pub to: f64,
+    pub steps: f64,
+    pub progress: f64,
+}
+
+// The playhead reached a step. `at` is the time into the period
+// passed to `Model::update` at which that happened.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StepEvent
+{
+    pub step: usize,
+    pub note: Option<Note>,
+    pub at: Duration,
+}
+
+// A beat the metronome clicks on, like a step event. The
+// first beat of a bar is 0.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BeatEvent
+{
+    pub beat: usize,
+    pub at: Duration,
+}
+
+// What happened during one `Model::update`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClockEvents
+{
+    pub steps: Vec<StepEvent>,
+    pub beats: Vec<BeatEvent>,
+}
+
+// Changes of the clock. Made on the model of the UI, they are
+// recorded to be replayed on the model driving the sequencer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClockRequest
+{
+    Play,
+    Pause,
+    Resume,
+    Stop,
+    Tempo(f64),
+    Ramp { to: f64, bars: usize },
+}
+
+// A step with its flags, as copied and pasted.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Step
+{
+    note: Option<Note>,
+    muted: bool,
+    skipped: bool,
+}
+
+// The clock state of a model, what the sequencer reports back.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Playhead
+{
+    transport: TransportState,
+    position: usize,
+    bpm: f64,
+    ramp: Option<Ramp>,
+    phase: f64,
+    tick: Option<i64>,
+}
+
+impl Playhead {
+    // Whether anything that shows differs, the phase in between
+    // steps doesn't.
+    pub fn moved(&self, other: &Playhead) -> bool
+    {
+        (self.transport, self.position, self.bpm, self.ramp) != (other.transport, other.position, other.bpm, other.ramp)
+    }
+}
+
+// What changed in the model, for the outputs interested in it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Change
+{
+    // The note, mute or skip state of a single step.
+    Step(usize),
+    // Possibly all of the steps.
+    Pattern,
+    Tempo,
+    Volume,
+    // The transport state, or its settings like count-in.
+    Transport,
+    Direction,
+    Density,
+    Scale,
+    Generator,
+    Metronome,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TransportState
 {
+    // Stopping rewinds, playing starts over from the first step.
     Stopped,
-    Playing
+    Playing,
+    // Holds the playhead where it is, to continue from there.
+    Paused,
+    // A bar of lead-in before playing starts.
+    CountIn,
 }
 
+impl TransportState {
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            TransportState::Stopped => "STOP",
+            TransportState::Playing => "PLAY",
+            TransportState::Paused => "PAUSE",
+            TransportState::CountIn => "COUNT",
+        }
+    }
+}
+
+// Views read the fields directly, but change them through
+// the methods, so the changes get reported.
+#[derive(Clone)]
 pub struct Model
 {
     pub bpm: f64,
+    pub ramp: Option<Ramp>,
     pub volume: f64,
     pub transport: TransportState,
+    // Whether playing from the start counts in first.
+    pub count_in: bool,
+    // The count-in always clicks, playing only with the metronome on.
+    pub metronome: bool,
+    // From 0 to 1.
+    pub click_level: f64,
+    // Whether clicks also go out as MIDI notes.
+    pub click_midi: bool,
     pub position: usize,
+    pub direction: Direction,
     pub notes: [Option<Note>; NOTE_COUNT],
-    elapsed: Duration
+    // Muted steps keep their note, but stay silent.
+    pub muted: [bool; NOTE_COUNT],
+    // Skipped steps are left out, the playhead jumps over them.
+    pub skipped: [bool; NOTE_COUNT],
+    // How many of the steps randomize fills, from 0 to 1.
+    pub density: f64,
+    pub scale: Scale,
+    pub generator: Generator,
+    step_clipboard: Option<Step>,
+    pattern_clipboard: Option<[Step; NOTE_COUNT]>,
+    // The playhead in steps since the start, fractional. It is
+    // negative while counting in.
+    phase: f64,
+    // The last step boundary the playhead crossed.
+    tick: Option<i64>,
+    requests: Vec<ClockRequest>,
+    changes: Vec<Change>,
 
 
 }
@@ -57,57 +316,571 @@ impl Default for Model
     {
         Self {
             bpm: 120.0,
+            ramp: None,
             volume: 5.0,
             transport: TransportState::Playing,
+            count_in: false,
+            metronome: false,
+            click_level: 0.7,
+            click_midi: false,
             position: 0,
+            direction: Direction::Forward,
             notes: [None; NOTE_COUNT],
-            elapsed: Duration::from_secs(0),
+            muted: [false; NOTE_COUNT],
+            skipped: [false; NOTE_COUNT],
+            density: 0.75,
+            scale: Scale::All,
+            generator: Generator::default(),
+            step_clipboard: None,
+            pattern_clipboard: None,
+            phase: 0.0,
+            tick: None,
+            requests: vec![],
+            changes: vec![],
         }
     }
 }
 
 impl Model {
-    pub fn update(&mut self, elapsed: Duration)
+    // Moves the playhead on, and reports every step boundary and
+    // beat it crossed on the way, however long the period was.
+    pub fn update(&mut self, elapsed: Duration) -> ClockEvents
+    {
+        let mut events = ClockEvents::default();
+        if self.moving() {
+            // Accumulating keeps the playhead in place when the tempo changes.
+            let rate = self.bpm / 60.0 * STEPS_PER_BEAT;
+            let start = self.phase;
+            let steps = elapsed.as_secs_f64() * rate;
+            self.phase += steps;
+            if self.phase >= 0.0 {
+                self.transport = TransportState::Playing;
+                self.advance_ramp(steps);
+            }
+            let playable: Vec<usize> = (0..NOTE_COUNT).filter(|step| !self.skipped[*step]).collect();
+            let first = self.tick.map_or(start.ceil() as i64, |tick| tick + 1);
+            for tick in first..=self.phase.floor() as i64 {
+                self.tick = Some(tick);
+                let at = if rate > 0.0 { ((tick as f64 - start) / rate).max(0.0) } else { 0.0 };
+                let at = Duration::from_secs_f64(at);
+                let steps_per_beat = STEPS_PER_BEAT as i64;
+                if tick % steps_per_beat == 0 && (self.metronome || tick < 0) {
+                    let beat = (tick / steps_per_beat).rem_euclid(BEATS_PER_BAR as i64) as usize;
+                    events.beats.push(BeatEvent { beat, at });
+                }
+                // With everything skipped, the playhead just stays put.
+                if tick < 0 || playable.is_empty() {
+                    continue;
+                }
+                self.position = playable[self.direction.index(tick as usize, playable.len())];
+                events.steps.push(StepEvent { step: self.position, note: self.sounding(), at });
+            }
+        }
+        events
+    }
+
+    // Whether the clock runs, counting in or playing.
+    pub fn moving(&self) -> bool
+    {
+        matches!(self.transport, TransportState::Playing | TransportState::CountIn)
+    }
+
+    // The beat of the bar the playhead is in, while moving.
+    pub fn beat(&self) -> Option<usize>
     {
         match self.transport {
-            TransportState::Playing => {
-                self.elapsed += elapsed;
-                self.position = ((self.elapsed.as_secs_f64() / 60.0 * self.bpm * 4.0).floor() as usize) % NOTE_COUNT;
-            },
-            _ => {}
+            TransportState::Playing | TransportState::CountIn => {
+                Some((self.phase / STEPS_PER_BEAT).floor().rem_euclid(BEATS_PER_BAR as f64) as usize)
+            }
+            _ => None,
         }
     }
 
+    // The note to be heard right now, if any.
+    pub fn sounding(&self) -> Option<Note>
+    {
+        match self.transport {
+            TransportState::Playing if !self.muted[self.position] && !self.skipped[self.position] => self.notes[self.position],
+            _ => None,
+        }
+    }
+
+    // The beats left to count in, while doing so.
+    pub fn count_in_beats(&self) -> Option<usize>
+    {
+        match self.transport {
+            TransportState::CountIn => Some((-self.phase / STEPS_PER_BEAT).ceil() as usize),
+            _ => None,
+        }
+    }
+
+    pub fn change_bpm(&mut self, delta: f64)
+    {
+        self.set_bpm(self.bpm + delta);
+    }
+
+    fn advance_ramp(&mut self, steps: f64)
+    {
+        if let Some(ramp) = &mut self.ramp {
+            ramp.progress = (ramp.progress + steps).min(ramp.steps);
+            let ratio = if ramp.steps > 0.0 { ramp.progress / ramp.steps } else { 1.0 };
+            self.bpm = ramp.from + (ramp.to - ramp.from) * ratio;
+            if ratio >= 1.0 {
+                self.ramp = None;
+            }
+        }
+    }
+
+    fn changed(&mut self, change: Change)
+    {
+        self.changes.push(change);
+    }
+
+    // Everything that changed since the last call, in order.
+    pub fn take_changes(&mut self) -> Vec<Change>
+    {
+        std::mem::take(&mut self.changes)
+    }
+
+    // Any manual tempo change ends a running ramp.
+    pub fn set_bpm(&mut self, bpm: f64)
+    {
+        self.request(ClockRequest::Tempo(bpm));
+    }
+
+    pub fn start_ramp(&mut self, to: f64, bars: usize)
+    {
+        self.request(ClockRequest::Ramp { to, bars });
+    }
+
     pub fn speedup(&mut self)
     {
-        self.bpm = (self.bpm + 1.0).clamp(0.0, 300.0);
+        self.change_bpm(1.0);
     }
 
     pub fn slowdown(&mut self)
     {
-        self.bpm = (self.bpm - 1.0).clamp(0.0, 300.0);
+        self.change_bpm(-1.0);
     }
 
+    // Kept to the fine steps, so adding them up doesn't drift.
+    pub fn change_volume(&mut self, delta: f64)
+    {
+        self.volume = ((self.volume + delta) * 10.0).round().clamp(0.0, MAX_VOLUME * 10.0) / 10.0;
+        self.changed(Change::Volume);
+    }
 
     pub fn pumpup(&mut self)
     {
-        self.volume = (self.volume + 1.0).clamp(0.0, 11.0);
+        self.change_volume(1.0);
     }
 
     pub fn receive_complaint(&mut self)
     {
-        self.volume = (self.volume - 1.0).clamp(0.0, 11.0);
+        self.change_volume(-1.0);
     }
 
-    pub fn toggle_transport(&mut self)
+    fn step(&self, step: usize) -> Step
     {
-        match self.transport {
-            TransportState::Playing => {
+        Step { note: self.notes[step], muted: self.muted[step], skipped: self.skipped[step] }
+    }
+
+    fn put_step(&mut self, step: usize, state: Step)
+    {
+        self.notes[step] = state.note;
+        self.muted[step] = state.muted;
+        self.skipped[step] = state.skipped;
+    }
+
+    // Copies, and pastes, take the mute and skip state along.
+    pub fn copy_step(&mut self, from: usize, to: usize)
+    {
+        self.put_step(to, self.step(from));
+        self.changed(Change::Step(to));
+    }
+
+    pub fn set_note(&mut self, step: usize, note: Option<Note>)
+    {
+        self.notes[step] = note;
+        self.changed(Change::Step(step));
+    }
+
+    pub fn clear_pattern(&mut self)
+    {
+        self.notes = [None; NOTE_COUNT];
+        self.muted = [false; NOTE_COUNT];
+        self.skipped = [false; NOTE_COUNT];
+        self.changed(Change::Pattern);
+    }
+
+    pub fn copy_to_clipboard(&mut self, step: usize)
+    {
+        self.step_clipboard = Some(self.step(step));
+    }
+
+    // Returns false if there was nothing to paste.
+    pub fn paste_from_clipboard(&mut self, step: usize) -> bool
+    {
+        match self.step_clipboard {
+            Some(state) => {
+                self.put_step(step, state);
+                self.changed(Change::Step(step));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn copy_pattern(&mut self)
+    {
+        self.pattern_clipboard = Some(std::array::from_fn(|step| self.step(step)));
+    }
+
+    // Returns false if there was nothing to paste.
+    pub fn paste_pattern(&mut self) -> bool
+    {
+        match self.pattern_clipboard {
+            Some(steps) => {
+                for (step, state) in steps.into_iter().enumerate() {
+                    self.put_step(step, state);
+                }
+                self.changed(Change::Pattern);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Rotations move the mute and skip state along with the notes.
+    pub fn rotate_left(&mut self)
+    {
+        self.notes.rotate_left(1);
+        self.muted.rotate_left(1);
+        self.skipped.rotate_left(1);
+        self.changed(Change::Pattern);
+    }
+
+    pub fn rotate_right(&mut self)
+    {
+        self.notes.rotate_right(1);
+        self.muted.rotate_right(1);
+        self.skipped.rotate_right(1);
+        self.changed(Change::Pattern);
+    }
+
+    pub fn reverse(&mut self)
+    {
+        self.notes.reverse();
+        self.muted.reverse();
+        self.skipped.reverse();
+        self.changed(Change::Pattern);
+    }
+
+    pub fn change_density(&mut self, delta: f64)
+    {
+        self.density = (self.density + delta).clamp(0.0, 1.0);
+        self.changed(Change::Density);
+    }
+
+    pub fn randomize(&mut self)
+    {
+        let mut rng = rand::thread_rng();
+        for note in self.notes.iter_mut() {
+            *note = if rng.gen_bool(self.density) { Some(self.scale.sample(&mut rng)) } else { None };
+        }
+        self.changed(Change::Pattern);
+    }
+
+    // Replace the pattern with the one of the generator.
+    pub fn generate(&mut self)
+    {
+        self.notes = self.generator.generate(self.scale);
+        self.muted = [false; NOTE_COUNT];
+        self.skipped = [false; NOTE_COUNT];
+        self.changed(Change::Pattern);
+    }
+
+    // New generator settings take effect right away.
+    pub fn set_generator(&mut self, generator: Generator)
+    {
+        self.generator = generator;
+        self.changed(Change::Generator);
+        self.generate();
+    }
+
+    pub fn next_scale(&mut self)
+    {
+        self.scale = self.scale.next();
+        self.changed(Change::Scale);
+    }
+
+    pub fn set_direction(&mut self, direction: Direction)
+    {
+        self.direction = direction;
+        self.changed(Change::Direction);
+    }
+
+    pub fn toggle_mute(&mut self, step: usize)
+    {
+        self.muted[step] = !self.muted[step];
+        self.changed(Change::Step(step));
+    }
+
+    pub fn toggle_skip(&mut self, step: usize)
+    {
+        self.skipped[step] = !self.skipped[step];
+        self.changed(Change::Step(step));
+    }
+
+    fn rewind(&mut self)
+    {
+        self.phase = 0.0;
+        self.tick = None;
+        self.position = 0;
+    }
+
+    // Applied right away, so the UI doesn't lag behind, and
+    // kept to be sent to the sequencer.
+    fn request(&mut self, request: ClockRequest)
+    {
+        self.apply(request);
+        self.requests.push(request);
+        self.changed(match request {
+            ClockRequest::Tempo(_) | ClockRequest::Ramp { .. } => Change::Tempo,
+            _ => Change::Transport,
+        });
+    }
+
+    pub fn take_requests(&mut self) -> Vec<ClockRequest>
+    {
+        std::mem::take(&mut self.requests)
+    }
+
+    pub fn apply(&mut self, request: ClockRequest)
+    {
+        match request {
+            // From the first step, after counting in if enabled.
+            ClockRequest::Play => {
+                self.rewind();
+                if self.count_in {
+                    self.phase = -STEPS_PER_BAR;
+                    self.transport = TransportState::CountIn;
+                } else {
+                    self.transport = TransportState::Playing;
+                }
+            }
+            ClockRequest::Pause => {
+                if self.transport == TransportState::Playing {
+                    self.transport = TransportState::Paused;
+                }
+            }
+            // Continues where paused, anything else plays from the start.
+            ClockRequest::Resume => match self.transport {
+                TransportState::Paused => self.transport = TransportState::Playing,
+                TransportState::Stopped => self.apply(ClockRequest::Play),
+                _ => (),
+            },
+            ClockRequest::Stop => {
                 self.transport = TransportState::Stopped;
+                self.rewind();
             }
-            TransportState::Stopped => {
-                self.transport = TransportState::Playing;
+            // Kept to tenths, like the finest steps, so they don't drift.
+            ClockRequest::Tempo(bpm) => {
+                self.ramp = None;
+                self.bpm = ((bpm * 10.0).round() / 10.0).clamp(0.0, MAX_BPM);
+            }
+            ClockRequest::Ramp { to, bars } => {
+                self.ramp = Some(Ramp {
+                    from: self.bpm,
+                    to: ((to * 10.0).round() / 10.0).clamp(0.0, MAX_BPM),
+                    steps: bars as f64 * STEPS_PER_BAR,
+                    progress: 0.0,
+                });
             }
         }
     }
+
+    pub fn playhead(&self) -> Playhead
+    {
+        Playhead {
+            transport: self.transport,
+            position: self.position,
+            bpm: self.bpm,
+            ramp: self.ramp,
+            phase: self.phase,
+            tick: self.tick,
+        }
+    }
+
+    // The clock state is the sequencer's, following it isn't a
+    // change of the model. The changes were reported when requested.
+    pub fn follow(&mut self, playhead: &Playhead)
+    {
+        self.transport = playhead.transport;
+        self.position = playhead.position;
+        self.bpm = playhead.bpm;
+        self.ramp = playhead.ramp;
+        self.phase = playhead.phase;
+        self.tick = playhead.tick;
+    }
+
+    // Takes everything but the clock state from the other model.
+    pub fn adopt_settings(&mut self, other: &Model)
+    {
+        let playhead = self.playhead();
+        *self = other.clone();
+        self.requests.clear();
+        self.changes.clear();
+        self.follow(&playhead);
+    }
+
+    pub fn play(&mut self)
+    {
+        self.request(ClockRequest::Play);
+    }
+
+    pub fn pause(&mut self)
+    {
+        self.request(ClockRequest::Pause);
+    }
+
+    pub fn resume(&mut self)
+    {
+        self.request(ClockRequest::Resume);
+    }
+
+    pub fn stop(&mut self)
+    {
+        self.request(ClockRequest::Stop);
+    }
+
+    pub fn toggle_transport(&mut self)
+    {
+        match self.transport {
+            TransportState::Playing => self.pause(),
+            TransportState::Paused | TransportState::Stopped => self.resume(),
+            TransportState::CountIn => self.stop(),
+        }
+    }
+
+    pub fn toggle_count_in(&mut self)
+    {
+        self.count_in = !self.count_in;
+        self.changed(Change::Transport);
+    }
+
+    pub fn toggle_metronome(&mut self)
+    {
+        self.metronome = !self.metronome;
+        self.changed(Change::Metronome);
+    }
+
+    pub fn toggle_click_midi(&mut self)
+    {
+        self.click_midi = !self.click_midi;
+        self.changed(Change::Metronome);
+    }
+
+    pub fn change_click_level(&mut self, delta: f64)
+    {
+        self.click_level = (self.click_level + delta).clamp(0.0, 1.0);
+        self.changed(Change::Metronome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs the clock through the periods, collecting everything it
+    // reports. Events always fall within the period they're reported for.
+    fn run(model: &mut Model, periods: impl IntoIterator<Item=Duration>) -> ClockEvents
+    {
+        let mut events = ClockEvents::default();
+        for period in periods {
+            let update = model.update(period);
+            assert!(update.steps.iter().all(|step| step.at <= period));
+            assert!(update.beats.iter().all(|beat| beat.at <= period));
+            events.steps.extend(update.steps);
+            events.beats.extend(update.beats);
+        }
+        events
+    }
+
+    fn steps(events: &ClockEvents) -> Vec<usize>
+    {
+        events.steps.iter().map(|event| event.step).collect()
+    }
+
+    #[test]
+    fn every_step_once_with_long_and_short_periods()
+    {
+        let mut model = Model::default();
+        // 120 BPM are 8 steps a second, so 100 times 303ms are 242.4 steps.
+        let periods = (0..100).flat_map(|_| [Duration::from_millis(3), Duration::from_millis(300)]);
+        let events = run(&mut model, periods);
+        let expected: Vec<usize> = (0..=242).map(|tick| tick % NOTE_COUNT).collect();
+        assert_eq!(steps(&events), expected);
+    }
+
+    #[test]
+    fn fine_tempo_steps_dont_drift()
+    {
+        let mut model = Model::default();
+        for _ in 0..7 {
+            model.change_bpm(0.1);
+        }
+        assert_eq!(model.bpm, 120.7);
+        for _ in 0..7 {
+            model.change_bpm(-0.1);
+        }
+        assert_eq!(model.bpm, 120.0);
+    }
+
+    #[test]
+    fn tempo_change_keeps_the_playhead_in_place()
+    {
+        let mut model = Model::default();
+        // 8.4 steps at 120 BPM, then 4 more at 60.
+        let mut events = run(&mut model, (0..21).map(|_| Duration::from_millis(50)));
+        model.apply(ClockRequest::Tempo(60.0));
+        events.steps.extend(run(&mut model, (0..10).map(|_| Duration::from_millis(100))).steps);
+        let expected: Vec<usize> = (0..=12).map(|tick| tick % NOTE_COUNT).collect();
+        assert_eq!(steps(&events), expected);
+    }
+
+    #[test]
+    fn skipped_steps_are_jumped_over()
+    {
+        let mut model = Model::default();
+        model.skipped[1] = true;
+        model.skipped[2] = true;
+        let events = run(&mut model, (0..17).map(|_| Duration::from_millis(70)));
+        // 9.52 steps over the six playable ones.
+        assert_eq!(steps(&events), vec![0, 3, 4, 5, 6, 7, 0, 3, 4, 5]);
+    }
+
+    #[test]
+    fn ping_pong_doesnt_repeat_the_turning_points()
+    {
+        let mut model = Model { direction: Direction::PingPong, ..Default::default() };
+        // 16.8 steps, there and back again.
+        let events = run(&mut model, (0..21).map(|_| Duration::from_millis(100)));
+        assert_eq!(steps(&events), vec![0, 1, 2, 3, 4, 5, 6, 7, 6, 5, 4, 3, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn count_in_clicks_a_bar_before_the_first_step()
+    {
+        let mut model = Model { count_in: true, ..Default::default() };
+        model.apply(ClockRequest::Play);
+        assert_eq!(model.transport, TransportState::CountIn);
+        assert_eq!(model.count_in_beats(), Some(BEATS_PER_BAR));
+        // A bar of 16 steps, and 0.8 steps into the pattern.
+        let events = run(&mut model, (0..30).map(|_| Duration::from_millis(70)));
+        let beats: Vec<usize> = events.beats.iter().map(|beat| beat.beat).collect();
+        assert_eq!(beats, vec![0, 1, 2, 3]);
+        assert_eq!(steps(&events), vec![0]);
+        assert_eq!(model.transport, TransportState::Playing);
+    }
 }
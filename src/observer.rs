@@ -0,0 +1,25 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::model::Change;
+
+// Hands the changes of the model to every output that subscribed,
+// so outputs don't have to poll the model or know about the views.
+#[derive(Default)]
+pub struct Observers {
+    subscribers: Vec<Sender<Change>>,
+}
+
+impl Observers {
+    pub fn subscribe(&mut self) -> Receiver<Change>
+    {
+        let (sender, receiver) = unbounded();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    pub fn publish(&mut self, changes: &[Change])
+    {
+        // Subscribers dropping their receiver unsubscribe.
+        self.subscribers.retain(|subscriber| changes.iter().all(|change| subscriber.send(*change).is_ok()));
+    }
+}
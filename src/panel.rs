@@ -0,0 +1,391 @@
+use std::{io::{Read, ErrorKind}, sync::Arc, thread, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use bytes::{Buf, BufMut, BytesMut};
+use crossbeam_channel::{unbounded, Receiver};
+use eframe::{egui::Key, epaint::Color32};
+use serial2::SerialPort;
+
+use crate::led::LedFrame;
+use crate::segment::{self, TextFrame, DIGITS, LCD_COLUMNS, LCD_LINES};
+use crate::damage::Waker;
+
+// The serial control-surface protocol.
+//
+// Every message is a single line of hex-encoded bytes, terminated
+// by a newline. The first byte is the command, followed by its
+// payload. Hex keeps the stream readable with a terminal program
+// attached to the port, which helps a lot when debugging hardware.
+//
+//   01 <button> <pressed>      panel -> host
+//   02 <encoder> <delta:i8>    panel -> host
+//   10 <led> <r> <g> <b>       host -> panel
+//   11 <x> <y> <r> <g> <b>     host -> panel
+//   12 <segments:4>            host -> panel
+//   13 <line> <chars:16>       host -> panel
+
+const CMD_BUTTON: u8 = 0x01;
+const CMD_ENCODER: u8 = 0x02;
+const CMD_LED: u8 = 0x10;
+const CMD_PAD: u8 = 0x11;
+const CMD_SEGMENTS: u8 = 0x12;
+const CMD_LCD: u8 = 0x13;
+
+pub const BAUD_RATE: u32 = 115200;
+
+// What a panel button does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    // Stands in for a key of the keyboard.
+    Key(Key),
+    // Modifier for fine encoder adjustments.
+    Fine,
+    // Modifier for the secondary functions of all controls.
+    Shift,
+    // Leaves dialogs and sub-pages.
+    Back,
+}
+
+// The buttons of the panel, in the order of their ids.
+pub const BUTTONS: [(Control, &str); 25] = [
+    (Control::Key(Key::Num1), "1"),
+    (Control::Key(Key::Num2), "2"),
+    (Control::Key(Key::Num3), "3"),
+    (Control::Key(Key::Num4), "4"),
+    (Control::Key(Key::Num5), "5"),
+    (Control::Key(Key::Num6), "6"),
+    (Control::Key(Key::Num7), "7"),
+    (Control::Key(Key::Num8), "8"),
+    (Control::Key(Key::ArrowLeft), "<"),
+    (Control::Key(Key::ArrowRight), ">"),
+    (Control::Key(Key::V), "VOL"),
+    (Control::Key(Key::P), "PLAY"),
+    (Control::Fine, "FINE"),
+    (Control::Shift, "SHIFT"),
+    (Control::Key(Key::M), "MUTE"),
+    (Control::Back, "BACK"),
+    (Control::Key(Key::C), "CLEAR"),
+    (Control::Key(Key::E), "EDIT"),
+    (Control::Key(Key::G), "GEN"),
+    (Control::Key(Key::O), "DIR"),
+    (Control::Key(Key::T), "TAP"),
+    (Control::Key(Key::R), "RAMP"),
+    (Control::Key(Key::S), "STOP"),
+    (Control::Key(Key::K), "COUNT"),
+    (Control::Key(Key::L), "CLICK"),
+];
+
+// Buttons with a LED underneath use the button id as LED id.
+pub const LED_PLAY: u8 = 11;
+pub const LED_SHIFT: u8 = 13;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Message {
+    Button { id: u8, pressed: bool },
+    Encoder { id: u8, delta: i8 },
+    Led { id: u8, color: Color32 },
+    Pad { x: u8, y: u8, color: Color32 },
+    Segments { digits: [u8; DIGITS] },
+    Lcd { line: u8, text: [u8; LCD_COLUMNS] },
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8>
+    {
+        let mut payload = BytesMut::with_capacity(2 + LCD_COLUMNS);
+        match *self {
+            Message::Button { id, pressed } => {
+                payload.put_u8(CMD_BUTTON);
+                payload.put_u8(id);
+                payload.put_u8(pressed as u8);
+            }
+            Message::Encoder { id, delta } => {
+                payload.put_u8(CMD_ENCODER);
+                payload.put_u8(id);
+                payload.put_i8(delta);
+            }
+            Message::Led { id, color } => {
+                payload.put_u8(CMD_LED);
+                payload.put_u8(id);
+                payload.put_slice(&[color.r(), color.g(), color.b()]);
+            }
+            Message::Pad { x, y, color } => {
+                payload.put_u8(CMD_PAD);
+                payload.put_u8(x);
+                payload.put_u8(y);
+                payload.put_slice(&[color.r(), color.g(), color.b()]);
+            }
+            Message::Segments { digits } => {
+                payload.put_u8(CMD_SEGMENTS);
+                payload.put_slice(&digits);
+            }
+            Message::Lcd { line, text } => {
+                payload.put_u8(CMD_LCD);
+                payload.put_u8(line);
+                payload.put_slice(&text);
+            }
+        }
+        let mut line = hex::encode(payload).into_bytes();
+        line.push(b'\n');
+        line
+    }
+
+    pub fn decode(line: &[u8]) -> anyhow::Result<Self>
+    {
+        let raw = hex::decode(line.trim_ascii()).context("malformed hex")?;
+        let mut payload = &raw[..];
+        if !payload.has_remaining() {
+            bail!("empty message");
+        }
+        let cmd = payload.get_u8();
+        let expected = match cmd {
+            CMD_BUTTON => 2,
+            CMD_ENCODER => 2,
+            CMD_LED => 4,
+            CMD_PAD => 5,
+            CMD_SEGMENTS => DIGITS,
+            CMD_LCD => 1 + LCD_COLUMNS,
+            _ => bail!("unknown command {:02x}", cmd),
+        };
+        if payload.remaining() != expected {
+            bail!("wrong payload length {} for {:02x}", payload.remaining(), cmd);
+        }
+        Ok(match cmd {
+            CMD_BUTTON => Message::Button { id: payload.get_u8(), pressed: payload.get_u8() != 0 },
+            CMD_ENCODER => Message::Encoder { id: payload.get_u8(), delta: payload.get_i8() },
+            CMD_LED => Message::Led {
+                id: payload.get_u8(),
+                color: Color32::from_rgb(payload.get_u8(), payload.get_u8(), payload.get_u8()),
+            },
+            CMD_PAD => Message::Pad {
+                x: payload.get_u8(),
+                y: payload.get_u8(),
+                color: Color32::from_rgb(payload.get_u8(), payload.get_u8(), payload.get_u8()),
+            },
+            CMD_SEGMENTS => {
+                let mut digits = [0; DIGITS];
+                payload.copy_to_slice(&mut digits);
+                Message::Segments { digits }
+            }
+            _ => {
+                let line = payload.get_u8();
+                let mut text = [0; LCD_COLUMNS];
+                payload.copy_to_slice(&mut text);
+                Message::Lcd { line, text }
+            }
+        })
+    }
+}
+
+// Collects bytes from the wire, and hands out complete messages.
+#[derive(Default)]
+pub struct Decoder {
+    buffer: BytesMut,
+}
+
+impl Decoder {
+    pub fn extend(&mut self, data: &[u8])
+    {
+        self.buffer.extend_from_slice(data);
+    }
+
+    pub fn next_message(&mut self) -> Option<anyhow::Result<Message>>
+    {
+        let end = self.buffer.iter().position(|b| *b == b'\n')?;
+        let line = self.buffer.split_to(end + 1);
+        Some(Message::decode(&line))
+    }
+}
+
+// Reads messages from the given source until it fails, and forwards
+// them to the returned channel, waking up the UI. Timeouts are
+// expected from serial ports and just retried.
+pub fn spawn_reader<R: Read + Send + 'static>(mut source: R, waker: Waker) -> Receiver<Message>
+{
+    let (sender, receiver) = unbounded();
+    thread::spawn(move || {
+        let mut decoder = Decoder::default();
+        let mut buffer = [0; 256];
+        loop {
+            match source.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(count) => decoder.extend(&buffer[..count]),
+                Err(err) if matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted | ErrorKind::WouldBlock) => continue,
+                Err(err) => {
+                    eprintln!("panel: read failed: {}", err);
+                    break;
+                }
+            }
+            while let Some(message) = decoder.next_message() {
+                match message {
+                    Ok(message) => {
+                        if sender.send(message).is_err() {
+                            return;
+                        }
+                        waker.wake();
+                    }
+                    Err(err) => eprintln!("panel: dropping message: {:#}", err),
+                }
+            }
+        }
+    });
+    receiver
+}
+
+// Adapter so the reader thread and the UI can share one port.
+struct PortReader(Arc<SerialPort>);
+
+impl Read for PortReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+// The host side of a connection to a (possibly emulated) panel.
+pub struct Panel {
+    port: Arc<SerialPort>,
+    messages: Receiver<Message>,
+    leds: [Option<Color32>; BUTTONS.len()],
+    pads: Option<LedFrame>,
+    digits: Option<[u8; DIGITS]>,
+    lines: [Option<String>; LCD_LINES],
+}
+
+impl Panel {
+    pub fn open(path: &str, waker: Waker) -> anyhow::Result<Self>
+    {
+        let mut port = SerialPort::open(path, BAUD_RATE)
+            .with_context(|| format!("can't open panel at {}", path))?;
+        port.set_read_timeout(Duration::from_millis(100))?;
+        let port = Arc::new(port);
+        let messages = spawn_reader(PortReader(port.clone()), waker);
+        Ok(Self { port, messages, leds: [None; BUTTONS.len()], pads: None, digits: None, lines: Default::default() })
+    }
+
+    // All messages that arrived since the last call.
+    pub fn messages(&self) -> Vec<Message>
+    {
+        self.messages.try_iter().collect()
+    }
+
+    // Set a LED. Only changes are transmitted.
+    pub fn led(&mut self, id: u8, color: Color32) -> anyhow::Result<()>
+    {
+        let slot = self.leds.get_mut(id as usize).ok_or_else(|| anyhow!("no LED {}", id))?;
+        if *slot != Some(color) {
+            self.port.write_all(&Message::Led { id, color }.encode())?;
+            *slot = Some(color);
+        }
+        Ok(())
+    }
+
+    // Bring the pad grid up to date with the frame. The first
+    // frame is sent completely, afterwards only changed pads.
+    pub fn pads(&mut self, frame: &LedFrame) -> anyhow::Result<()>
+    {
+        let mut data = vec![];
+        let changes: Vec<_> = match &self.pads {
+            Some(previous) => frame.changes(previous).collect(),
+            None => frame.iter().collect(),
+        };
+        for (x, y, color) in changes {
+            data.extend(Message::Pad { x: x as u8, y: y as u8, color }.encode());
+        }
+        if !data.is_empty() {
+            self.port.write_all(&data)?;
+        }
+        self.pads = Some(frame.clone());
+        Ok(())
+    }
+
+    // Show the text frame on both the 7-segment and the
+    // character display. Again only changes are transmitted.
+    pub fn text(&mut self, frame: &TextFrame) -> anyhow::Result<()>
+    {
+        let digits = segment::seven_segment(frame);
+        if self.digits != Some(digits) {
+            self.port.write_all(&Message::Segments { digits }.encode())?;
+            self.digits = Some(digits);
+        }
+        for (line, text) in segment::character_lcd(frame).into_iter().enumerate() {
+            if self.lines[line].as_ref() != Some(&text) {
+                let mut chars = [b' '; LCD_COLUMNS];
+                for (c, b) in chars.iter_mut().zip(text.bytes()) {
+                    *c = if b.is_ascii() { b } else { b'?' };
+                }
+                self.port.write_all(&Message::Lcd { line: line as u8, text: chars }.encode())?;
+                self.lines[line] = Some(text);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message>
+    {
+        let mut text = [b' '; LCD_COLUMNS];
+        text[..4].copy_from_slice(b"BPM ");
+        vec![
+            Message::Button { id: 13, pressed: true },
+            Message::Button { id: 0, pressed: false },
+            Message::Encoder { id: 0, delta: -3 },
+            Message::Encoder { id: 1, delta: 127 },
+            Message::Led { id: LED_PLAY, color: Color32::from_rgb(0, 200, 0) },
+            Message::Pad { x: 7, y: 3, color: Color32::from_rgb(0x9b, 0x5f, 0xe0) },
+            Message::Segments { digits: [0x3f, 0x06, 0x5b, 0x4f] },
+            Message::Lcd { line: 1, text },
+        ]
+    }
+
+    #[test]
+    fn messages_survive_the_round_trip()
+    {
+        for message in messages() {
+            let line = message.encode();
+            assert_eq!(line.last(), Some(&b'\n'));
+            assert_eq!(Message::decode(&line).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected()
+    {
+        // A LED without its blue component.
+        assert!(Message::decode(b"100b00c8\n").is_err());
+        // A button with an extra byte.
+        assert!(Message::decode(b"010d0100\n").is_err());
+        assert!(Message::decode(b"7f00\n").is_err());
+        assert!(Message::decode(b"\n").is_err());
+        assert!(Message::decode(b"01zz01\n").is_err());
+    }
+
+    #[test]
+    fn decoder_waits_for_complete_lines()
+    {
+        let mut decoder = Decoder::default();
+        let line = Message::Encoder { id: 0, delta: 2 }.encode();
+        let (first, second) = line.split_at(3);
+        decoder.extend(first);
+        assert!(decoder.next_message().is_none());
+        decoder.extend(second);
+        assert_eq!(decoder.next_message().unwrap().unwrap(), Message::Encoder { id: 0, delta: 2 });
+        assert!(decoder.next_message().is_none());
+
+        // Several messages in one go, a bad one in between.
+        let mut data = vec![];
+        for message in messages() {
+            data.extend(message.encode());
+            data.extend(b"7f00\n");
+        }
+        decoder.extend(&data);
+        for message in messages() {
+            assert_eq!(decoder.next_message().unwrap().unwrap(), message);
+            assert!(decoder.next_message().unwrap().is_err());
+        }
+        assert!(decoder.next_message().is_none());
+    }
+}
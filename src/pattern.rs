@@ -0,0 +1,120 @@
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Color32, Rounding, vec2}, emath::Align2};
+
+use crate::view::{View, ModalHandle};
+use crate::layout;
+use crate::damage::{Damage, Shown};
+use crate::event::Event;
+use crate::gesture::Gesture;
+use crate::dialog::{ConfirmView, Toasts};
+use crate::matrix::STEP_KEYS;
+use crate::model::{Model, Scale, NOTE_COUNT};
+
+const DENSITY_STEP: f64 = 1.0 / NOTE_COUNT as f64;
+
+const HELP: [&str; 3] = [
+    "</> rotate  R reverse  D dice",
+    "S scale  ^/v density",
+    "C copy  1-8 copy step  +shift paste",
+];
+
+// A sub-page with operations on the whole pattern. Lives on
+// the modal stack, and is left with Back, or E again.
+pub struct PatternView {
+    modals: ModalHandle,
+    toasts: Toasts,
+    rect: Rect,
+    shown: Shown<(f64, Scale)>,
+}
+
+impl PatternView {
+    pub fn new(modals: ModalHandle, toasts: Toasts) -> Self
+    {
+        Self { modals, toasts, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+
+    fn feed_steps(&mut self, event: Event, model: &mut Model) -> bool
+    {
+        for (step, key) in STEP_KEYS.iter().enumerate() {
+            if event.modifiers().shift && self.pressed_once(*key, event.clone()) {
+                if model.paste_from_clipboard(step) {
+                    self.toasts.show(&format!("Pasted to step {}", step + 1));
+                }
+                return true;
+            } else if !event.modifiers().shift && matches!(&event, Event::Gesture { gesture: Gesture::Tap(k), .. } if k == key) {
+                model.copy_to_clipboard(step);
+                self.toasts.show(&format!("Copied step {}", step + 1));
+                return true;
+            } else if self.used(*key, event.clone()) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl View for PatternView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed_once(Key::E, event.clone()) {
+            self.modals.pop();
+        } else if self.pressed(Key::ArrowLeft, event.clone()) {
+            model.rotate_left();
+        } else if self.pressed(Key::ArrowRight, event.clone()) {
+            model.rotate_right();
+        } else if self.pressed_once(Key::R, event.clone()) {
+            model.reverse();
+        } else if self.pressed_once(Key::D, event.clone()) {
+            // Dicing throws the pattern away, so ask first.
+            let confirm = ConfirmView::new("Dice pattern?", self.modals.clone(), |model| model.randomize());
+            self.modals.push(Box::new(confirm));
+        } else if self.pressed_once(Key::S, event.clone()) {
+            model.next_scale();
+        } else if self.pressed(Key::ArrowUp, event.clone()) {
+            model.change_density(DENSITY_STEP);
+        } else if self.pressed(Key::ArrowDown, event.clone()) {
+            model.change_density(-DENSITY_STEP);
+        } else if let Event::Encoder { delta, .. } = event {
+            model.change_density(delta as f64 * DENSITY_STEP);
+        } else if self.pressed_once(Key::C, event.clone()) {
+            if !event.modifiers().shift {
+                model.copy_pattern();
+                self.toasts.show("Pattern copied");
+            } else if model.paste_pattern() {
+                self.toasts.show("Pattern pasted");
+            }
+        } else {
+            return self.feed_steps(event, model);
+        }
+        true
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, model: &Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        let painter = ui.painter_at(rect);
+        let white = Color32::WHITE;
+        painter.rect_filled(rect, Rounding::none(), Color32::from_gray(20));
+        let scale = layout::scale(rect, layout::PAGE_DESIGN);
+        let rows = layout::rows(rect.shrink2(vec2(8.0, 2.0) * scale), 1 + HELP.len(), 0.0);
+        let status = format!("PATTERN  density {:.0}%  {}", model.density * 100.0, model.scale.name());
+        painter.text(rows[0].left_center(), Align2::LEFT_CENTER, status, layout::font(12.0, scale), white);
+        for (line, row) in HELP.iter().zip(&rows[1..]) {
+            painter.text(row.left_center(), Align2::LEFT_CENTER, line, layout::font(11.0, scale), Color32::GRAY);
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = layout::PAGE.rect(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.shown.update((model.density, model.scale)) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+}
@@ -0,0 +1,97 @@
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Color32, Rounding, vec2}, emath::Align2};
+
+use crate::view::{View, ModalHandle};
+use crate::layout;
+use crate::damage::{Damage, Shown};
+use crate::event::{Event, Modifiers};
+use crate::dialog::Toasts;
+use crate::model::{Model, MAX_BPM};
+
+const MAX_BARS: usize = 32;
+
+const HELP: [&str; 2] = [
+    "^/v target, shift x10, alt /10",
+    "</> bars  Enter start  R cancel",
+];
+
+// A sub-page setting up a tempo ramp: towards a target
+// BPM, over a number of bars.
+pub struct RampView {
+    modals: ModalHandle,
+    toasts: Toasts,
+    target: f64,
+    bars: usize,
+    rect: Rect,
+    shown: Shown<(f64, f64, usize)>,
+}
+
+impl RampView {
+    pub fn new(modals: ModalHandle, toasts: Toasts) -> Self
+    {
+        Self { modals, toasts, target: 0.0, bars: 4, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+
+    fn change_target(&mut self, delta: f64, modifiers: Modifiers)
+    {
+        let factor = if modifiers.shift { 10.0 } else if modifiers.fine { 0.1 } else { 1.0 };
+        self.target = ((self.target + delta * factor) * 10.0).round() / 10.0;
+        self.target = self.target.clamp(0.0, MAX_BPM);
+    }
+}
+
+impl View for RampView
+{
+    fn activate(&mut self, model: &Model) {
+        self.target = model.bpm.round();
+    }
+
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed_once(Key::R, event.clone()) {
+            self.modals.pop();
+        } else if self.pressed(Key::ArrowUp, event.clone()) {
+            self.change_target(1.0, event.modifiers());
+        } else if self.pressed(Key::ArrowDown, event.clone()) {
+            self.change_target(-1.0, event.modifiers());
+        } else if let Event::Encoder { delta, modifiers, .. } = event {
+            self.change_target(delta as f64, modifiers);
+        } else if self.pressed(Key::ArrowLeft, event.clone()) {
+            self.bars = (self.bars - 1).max(1);
+        } else if self.pressed(Key::ArrowRight, event.clone()) {
+            self.bars = (self.bars + 1).min(MAX_BARS);
+        } else if self.pressed_once(Key::Enter, event) {
+            model.start_ramp(self.target, self.bars);
+            self.toasts.show(&format!("Ramping to {:.1} BPM", self.target));
+            self.modals.pop();
+        }
+        true
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, model: &Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::none(), Color32::from_gray(20));
+        let scale = layout::scale(rect, layout::PAGE_DESIGN);
+        let rows = layout::rows(rect.shrink2(vec2(8.0, 2.0) * scale), 1 + HELP.len(), 0.0);
+        let status = format!("RAMP  {:.1} -> {:.1} BPM in {} bars", model.bpm, self.target, self.bars);
+        painter.text(rows[0].left_center(), Align2::LEFT_CENTER, status, layout::font(12.0, scale), Color32::WHITE);
+        for (line, row) in HELP.iter().zip(&rows[1..]) {
+            painter.text(row.left_center(), Align2::LEFT_CENTER, line, layout::font(11.0, scale), Color32::GRAY);
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = layout::PAGE.rect(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.shown.update((model.bpm, self.target, self.bars)) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+}
@@ -1,44 +1,154 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-use std::time::Duration;
-
-use eframe::egui::{Event, Key, Sense};
+use eframe::egui::{Key, Sense};
 use eframe::emath::Align2;
-use eframe::epaint::{Rect, Color32, FontId, vec2};
+use eframe::epaint::{Rect, Vec2, Color32, vec2};
+
+use crate::view::{View, ModalStack};
+use crate::ramp::RampView;
+use crate::help::HelpView;
+use crate::dialog::{ConfirmView, ToastView};
+use crate::pattern::PatternView;
+use crate::generator::GeneratorView;
+use crate::led::LedFrame;
+use crate::segment::{TextFrame, Field};
+use crate::event::{Event, Modifiers, Acceleration, Curve, MAIN_ENCODER};
+use crate::model::{Model, BEATS_PER_BAR};
+use crate::layout::{self, Place, Anchor};
+use crate::damage::{Damage, Shown};
+
+// Sweeping across the whole range should take a few quick turns.
+const BPM_ACCELERATION: Acceleration = Acceleration {
+    curve: Curve::Quadratic,
+    step: 1.0,
+    fine_step: 0.1,
+    threshold: 5.0,
+    gain: 0.05,
+    max_factor: 10.0,
+};
+
+// The BPM change of the arrow keys while shifted, or with fine steps.
+const BPM_COARSE_STEP: f64 = 10.0;
+const BPM_FINE_STEP: f64 = 0.1;
+
+// The status lines above the steps, on a screen designed for 320x200.
+const HEADER: Place = Place::new(Anchor::Top, 1.0, 0.2).offset(0.0, 0.1);
+const DESIGN: Vec2 = vec2(320.0, 200.0);
+
+// Tempos show with one decimal, integer input stays readable that way too.
+pub fn format_bpm(bpm: f64) -> String
+{
+    format!("{:.1}", bpm)
+}
+
+// While ramping, the target shows as well.
+fn format_tempo(model: &Model) -> String
+{
+    match &model.ramp {
+        Some(ramp) => format!("{} -> {}", format_bpm(model.bpm), format_bpm(ramp.to)),
+        None => format_bpm(model.bpm),
+    }
+}
+
+pub fn format_transport(model: &Model) -> String
+{
+    match model.count_in_beats() {
+        Some(beats) => format!("{} {}", model.transport.name(), beats),
+        None => model.transport.name().into(),
+    }
+}
+
+// The beat indicator, the first beat of the bar stands out.
+pub fn beat_color(i: usize, beat: usize) -> Color32
+{
+    if i != beat {
+        Color32::from_gray(60)
+    } else if i == 0 {
+        Color32::RED
+    } else {
+        Color32::WHITE
+    }
+}
 
-use crate::view::{View, ViewContainer, MomentaryTimedView};
-use crate::matrix::MatrixView;
-use crate::volume::VolumeView;
 pub struct Root
 {
-    children: ViewContainer,
+    children: ModalStack,
+    toasts: ToastView,
     rect: Rect,
+    modifiers: Modifiers,
+    // The keys of the base views, for the help page.
+    bindings: Vec<(String, String)>,
+    // Tempo, transport, beat and shift, as drawn last.
+    shown: Shown<(String, String, Option<usize>, bool)>,
 }
 
-impl Default for Root {
-    fn default() -> Self {
+impl Root {
+    // The base views come from the layout, the modal
+    // pages and global keys stay with the root.
+    pub fn new(base: Box<dyn View>, bindings: Vec<(String, String)>) -> Self {
         Self {
-            children: ViewContainer::new(vec![
-                Rc::new(RefCell::new(MatrixView::default())),
-                Rc::new(RefCell::new(MomentaryTimedView::new(
-                    Rc::new(RefCell::new(VolumeView::default())),
-                    Key::V, Duration::from_millis(1000))))
-            ]),
-            rect: Rect::from_min_max((0.0, 0.0).into(), (320.0, 200.0).into())
+            children: ModalStack::new(base),
+            toasts: ToastView::default(),
+            rect: Rect::NOTHING,
+            modifiers: Modifiers::default(),
+            bindings,
+            shown: Shown::default(),
         }
     }
-}
 
-impl Root {
     fn feed_internal(&mut self, event: Event, model: &mut crate::model::Model) -> bool {
-        if self.pressed(Key::P, event.clone()) {
-            model.toggle_transport();
+        if self.pressed_once(Key::P, event.clone()) {
+            if event.modifiers().shift {
+                model.play();
+            } else {
+                model.toggle_transport();
+            }
+            return true;
+        } else if self.pressed_once(Key::S, event.clone()) {
+            model.stop();
+            return true;
+        } else if self.pressed_once(Key::K, event.clone()) {
+            model.toggle_count_in();
+            self.toasts.handle().show(if model.count_in { "Count-in on" } else { "Count-in off" });
+            return true;
+        } else if self.pressed_once(Key::C, event.clone()) {
+            let toasts = self.toasts.handle();
+            let confirm = ConfirmView::new("Clear pattern?", self.children.handle(), move |model| {
+                model.clear_pattern();
+                toasts.show("Pattern cleared");
+            });
+            self.children.push(Box::new(confirm), model);
+            return true;
+        } else if self.pressed_once(Key::E, event.clone()) {
+            self.children.push(Box::new(PatternView::new(self.children.handle(), self.toasts.handle())), model);
+            return true;
+        } else if self.pressed_once(Key::G, event.clone()) {
+            self.children.push(Box::new(GeneratorView::new(self.children.handle())), model);
+            return true;
+        } else if self.pressed_once(Key::R, event.clone()) {
+            self.children.push(Box::new(RampView::new(self.children.handle(), self.toasts.handle())), model);
+            return true;
+        } else if self.pressed_once(Key::H, event.clone()) {
+            self.children.push(Box::new(HelpView::new(self.children.handle(), self.bindings.clone())), model);
             return true;
         } else if self.pressed(Key::ArrowLeft, event.clone()) {
-            model.slowdown();
+            if event.modifiers().shift {
+                model.change_bpm(-BPM_COARSE_STEP);
+            } else if event.modifiers().fine {
+                model.change_bpm(-BPM_FINE_STEP);
+            } else {
+                model.slowdown();
+            }
             return true;
         } else if self.pressed(Key::ArrowRight, event.clone()) {
-            model.speedup();
+            if event.modifiers().shift {
+                model.change_bpm(BPM_COARSE_STEP);
+            } else if event.modifiers().fine {
+                model.change_bpm(BPM_FINE_STEP);
+            } else {
+                model.speedup();
+            }
+            return true;
+        } else if let Event::Encoder { id: MAIN_ENCODER, delta, velocity, modifiers } = event {
+            model.change_bpm(BPM_ACCELERATION.change(delta, velocity, modifiers));
             return true;
         }
         false
@@ -48,6 +158,10 @@ impl Root {
 impl View for Root
 {
     fn feed(&mut self, event: Event, model: &mut crate::model::Model) -> bool {
+        // We show the modifiers, but children might be interested as well.
+        if let Event::Modifiers(modifiers) = event {
+            self.modifiers = modifiers;
+        }
         if !self.children.feed(event.clone(), model) {
             return self.feed_internal(event, model)
         }
@@ -59,10 +173,54 @@ impl View for Root
         let response = ui.allocate_rect(self.rect, Sense::click());
         let painter = ui.painter_at(self.rect);
         let white = Color32::from_white_alpha(255);
-        painter.text(self.rect.left_top() + vec2(8.0, 50.0), Align2::LEFT_CENTER, format!("{:}", model.bpm), FontId::monospace(15.0), white);
-        // then the child-views
+        let scale = layout::scale(self.rect, DESIGN);
+        let font = layout::font(15.0, scale);
+        let header = layout::rows(HEADER.rect(self.rect), 2, 0.0);
+        painter.text(header[1].left_center() + vec2(8.0 * scale, 0.0), Align2::LEFT_CENTER, format_tempo(model), font.clone(), white);
+        painter.text(header[1].right_center() + vec2(-70.0 * scale, 0.0), Align2::RIGHT_CENTER, format_transport(model), font.clone(), white);
+        if let Some(beat) = model.beat() {
+            for i in 0..BEATS_PER_BAR {
+                painter.circle_filled(header[0].right_center() + vec2(-56.0 + i as f32 * 14.0, 0.0) * scale, 4.0 * scale, beat_color(i, beat));
+            }
+        }
+        if self.modifiers.shift {
+            painter.text(header[1].right_center() + vec2(-8.0 * scale, 0.0), Align2::RIGHT_CENTER, "SHIFT", font, white);
+        }
+        // then the child-views, notifications on top of everything
         self.children.ui(ui, model);
+        self.toasts.ui(ui, model);
         response
     }
 
+    fn leds(&self, frame: &mut LedFrame, model: &crate::model::Model) {
+        self.children.leds(frame, model);
+    }
+
+    // The root fills the whole screen, and hands it on.
+    fn layout(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.children.layout(rect);
+        self.toasts.layout(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        let state = (format_tempo(model), format_transport(model), model.beat(), self.modifiers.shift);
+        if self.shown.update(state) {
+            damage.add(HEADER.rect(self.rect));
+        }
+        self.children.damage(model, damage);
+        self.toasts.damage(model, damage);
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+        self.children.invalidate();
+        self.toasts.invalidate();
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &crate::model::Model) {
+        frame.set(Field::Bpm, format_bpm(model.bpm));
+        self.children.text(frame, model);
+    }
+
 }
@@ -0,0 +1,128 @@
+// Text output for small segment and character displays.
+//
+// Views don't know about the concrete display. They write
+// labelled text fields into a `TextFrame`, in paint order like
+// with `View::ui`. The mappings below then decide what of that
+// fits onto the hardware.
+
+pub const DIGITS: usize = 4;
+pub const LCD_COLUMNS: usize = 16;
+pub const LCD_LINES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    Bpm,
+    Volume,
+    Direction,
+    Tap,
+    Click,
+}
+
+impl Field {
+    pub fn label(&self) -> &'static str
+    {
+        match self {
+            Field::Bpm => "BPM",
+            Field::Volume => "VOL",
+            Field::Direction => "DIR",
+            Field::Tap => "TAP",
+            Field::Click => "CLK",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextFrame {
+    fields: Vec<(Field, String)>,
+}
+
+impl TextFrame {
+    pub fn set(&mut self, field: Field, text: String)
+    {
+        self.fields.retain(|(f, _)| *f != field);
+        self.fields.push((field, text));
+    }
+
+    // The field painted first, usually the one of the root view.
+    pub fn base(&self) -> Option<&(Field, String)>
+    {
+        self.fields.first()
+    }
+
+    // The field painted last, e.g. of a view that popped up.
+    pub fn top(&self) -> Option<&(Field, String)>
+    {
+        self.fields.last()
+    }
+}
+
+// Segment bits: a..g are bits 0..6, the decimal point is bit 7.
+const DP: u8 = 0x80;
+
+fn segments_for(c: char) -> u8
+{
+    match c.to_ascii_uppercase() {
+        '0' | 'O' => 0x3f,
+        '1' => 0x06,
+        '2' => 0x5b,
+        '3' => 0x4f,
+        '4' => 0x66,
+        '5' | 'S' => 0x6d,
+        '6' => 0x7d,
+        '7' => 0x07,
+        '8' => 0x7f,
+        '9' => 0x6f,
+        'A' => 0x77,
+        'B' => 0x7c,
+        'C' => 0x39,
+        'D' => 0x5e,
+        'E' => 0x79,
+        'F' => 0x71,
+        'G' => 0x3d,
+        'I' => 0x06,
+        'L' => 0x38,
+        'N' => 0x54,
+        'P' => 0x73,
+        'R' => 0x50,
+        'U' | 'V' => 0x3e,
+        '-' => 0x40,
+        '_' => 0x08,
+        _ => 0x00,
+    }
+}
+
+// A 4-digit 7-segment display only fits one value, so it
+// shows the topmost field, right aligned.
+pub fn seven_segment(frame: &TextFrame) -> [u8; DIGITS]
+{
+    let mut digits: Vec<u8> = vec![];
+    if let Some((_, text)) = frame.top() {
+        for c in text.chars() {
+            match (c, digits.last_mut()) {
+                ('.', Some(last)) if *last & DP == 0 => *last |= DP,
+                ('.', _) => digits.push(DP),
+                _ => digits.push(segments_for(c)),
+            }
+        }
+    }
+    let mut res = [0; DIGITS];
+    // Keep the leftmost digits if the text is too long, they matter most.
+    digits.truncate(DIGITS);
+    res[DIGITS - digits.len()..].copy_from_slice(&digits);
+    res
+}
+
+// A 16x2 character display shows the base field on the first line,
+// and whatever is on top of it on the second.
+pub fn character_lcd(frame: &TextFrame) -> [String; LCD_LINES]
+{
+    let line = |field: Option<&(Field, String)>| {
+        let text = match field {
+            Some((field, text)) => format!("{:<4}{:>12}", field.label(), text),
+            None => String::new(),
+        };
+        format!("{:<width$.width$}", text, width = LCD_COLUMNS)
+    };
+    let top = if frame.fields.len() > 1 { frame.top() } else { None };
+    [line(frame.base()), line(top)]
+}
@@ -0,0 +1,135 @@
+use std::{thread, time::{Duration, Instant}};
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TryRecvError};
+
+use crate::audio::AudioEngine;
+use crate::midi::MidiOutput;
+use crate::model::{Change, ClockRequest, Model, Playhead};
+use crate::damage::Waker;
+
+// How often the sequencer thread advances the clock.
+const PERIOD: Duration = Duration::from_millis(2);
+
+enum Command {
+    Settings(Box<Model>),
+    Clock(ClockRequest),
+}
+
+// Applies a command to the thread's model, counting clock requests.
+fn handle(model: &mut Model, command: Command, handled: &mut u64)
+{
+    match command {
+        Command::Settings(settings) => model.adopt_settings(&settings),
+        Command::Clock(request) => {
+            model.apply(request);
+            *handled += 1;
+        }
+    }
+}
+
+// Tempo changes reach the thread as clock requests, and the
+// generator settings only matter once they changed the pattern.
+fn needs_settings(change: &Change) -> bool
+{
+    !matches!(change, Change::Tempo | Change::Density | Change::Scale | Change::Generator)
+}
+
+// The sequencer thread, as seen from the UI. It runs the clock on its
+// own copy of the model, and drives audio and MIDI from there, so
+// hitches of the UI don't matter to the timing.
+pub struct Sequencer {
+    commands: Sender<Command>,
+    changes: Receiver<Change>,
+    playheads: Receiver<(u64, Playhead)>,
+    // Clock requests sent, and the last playhead that accounts for all of them.
+    sent: u64,
+}
+
+impl Sequencer {
+    pub fn spawn(model: &Model, changes: Receiver<Change>, mut audio: Option<AudioEngine>, mut midi: Option<MidiOutput>, waker: Waker) -> Self
+    {
+        let (commands, receiver) = unbounded();
+        // Only the latest playhead is of interest, the thread replaces
+        // one the UI didn't pick up yet.
+        let (sender, playheads) = bounded(1);
+        let stale = playheads.clone();
+        let mut model = model.clone();
+        thread::spawn(move || {
+            let mut handled = 0;
+            let mut when = Instant::now();
+            loop {
+                // Standing still with nothing to feed, there's no point in
+                // polling, wait for the UI instead.
+                if !model.moving() && audio.is_none() {
+                    match receiver.recv() {
+                        Ok(command) => handle(&mut model, command, &mut handled),
+                        Err(_) => return,
+                    }
+                    // The clock didn't run while waiting.
+                    when = Instant::now();
+                }
+                loop {
+                    match receiver.try_recv() {
+                        Ok(command) => handle(&mut model, command, &mut handled),
+                        Err(TryRecvError::Empty) => break,
+                        // The UI is gone.
+                        Err(TryRecvError::Disconnected) => return,
+                    }
+                }
+                let now = Instant::now();
+                let elapsed = now - when;
+                when = now;
+                let events = model.update(elapsed);
+                // Nobody is interested in changes on this side.
+                model.take_changes();
+                if let Some(engine) = &mut audio {
+                    if let Err(err) = engine.render(elapsed, &events, &model) {
+                        // Most likely the reader went away, no point in going on.
+                        eprintln!("audio: {:#}", err);
+                        audio = None;
+                    }
+                }
+                if let Some(output) = &mut midi {
+                    if model.click_midi {
+                        for beat in &events.beats {
+                            if let Err(err) = output.click(beat.beat == 0, model.click_level) {
+                                eprintln!("midi: {:#}", err);
+                            }
+                        }
+                    }
+                }
+                let _ = stale.try_recv();
+                let _ = sender.try_send((handled, model.playhead()));
+                // The playhead moved on, there's something new to show.
+                if !events.steps.is_empty() || !events.beats.is_empty() {
+                    waker.wake();
+                }
+                thread::sleep(PERIOD);
+            }
+        });
+        Self { commands, changes, playheads, sent: 0 }
+    }
+
+    // Hands the changes of the UI's model to the sequencer, and
+    // updates it with the playhead in return. True if that moved on.
+    pub fn sync(&mut self, model: &mut Model) -> bool
+    {
+        // Settings first, so requests see the current ones, like count-in.
+        if self.changes.try_iter().filter(needs_settings).count() > 0 {
+            let _ = self.commands.send(Command::Settings(Box::new(model.clone())));
+        }
+        for request in model.take_requests() {
+            let _ = self.commands.send(Command::Clock(request));
+            self.sent += 1;
+        }
+        // Older playheads would undo requests the UI already applied.
+        if let Ok((handled, playhead)) = self.playheads.try_recv() {
+            if handled == self.sent {
+                let before = model.playhead();
+                model.follow(&playhead);
+                return model.playhead().moved(&before);
+            }
+        }
+        false
+    }
+}
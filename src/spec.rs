@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use eframe::egui::Key;
+use serde::Deserialize;
+
+use crate::view::{View, ViewContainer, MomentaryView, MomentaryTimedView, ToggleView};
+use crate::matrix::MatrixView;
+use crate::volume::VolumeView;
+use crate::direction::DirectionView;
+use crate::tap::TapTempoView;
+use crate::metronome::MetronomeView;
+use crate::status::StatusView;
+use crate::layout::Place;
+use crate::root::Root;
+use crate::display::Display;
+use crate::model::Model;
+
+// The layout the device ships with, used when no file is given.
+pub const DEFAULT_LAYOUT: &str = include_str!("../layout.toml");
+
+fn default_screen() -> [f32; 2]
+{
+    [320.0, 200.0]
+}
+
+fn default_refresh() -> f64
+{
+    60.0
+}
+
+// The displays of the device. The first one is the main display,
+// with the modal pages and the global keys, and it gets events
+// before the others.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutSpec {
+    displays: Vec<DisplaySpec>,
+}
+
+// The screen size in points, the refresh rate, and the base
+// views in the order they get events and draw.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DisplaySpec {
+    name: String,
+    #[serde(default = "default_screen")]
+    screen: [f32; 2],
+    #[serde(default = "default_refresh")]
+    refresh_hz: f64,
+    views: Vec<ViewSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "view", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum ViewSpec {
+    Container { children: Vec<ViewSpec> },
+    Rows { #[serde(default)] gap: f32, children: Vec<ViewSpec> },
+    Columns { #[serde(default)] gap: f32, children: Vec<ViewSpec> },
+    Grid { columns: usize, #[serde(default)] gap: f32, children: Vec<ViewSpec> },
+    Momentary { key: Key, child: Box<ViewSpec> },
+    Timed { key: Key, timeout_ms: u64, child: Box<ViewSpec> },
+    Toggle { key: Key, momentary_after_ms: Option<u64>, child: Box<ViewSpec> },
+    Matrix { steps: Place, mute_key: Key },
+    Volume { place: Option<Place> },
+    Direction { place: Option<Place> },
+    Metronome { place: Option<Place> },
+    Tap { key: Key, place: Option<Place> },
+    Status { place: Option<Place> },
+}
+
+impl LayoutSpec {
+    pub fn parse(text: &str) -> anyhow::Result<Self>
+    {
+        let spec: Self = toml::from_str(text).context("Invalid layout")?;
+        if spec.displays.is_empty() {
+            bail!("The layout has no displays");
+        }
+        if let Some(display) = spec.displays.iter().find(|display| !display.refresh_hz.is_finite() || display.refresh_hz <= 0.0) {
+            bail!("Display {} needs a positive refresh rate", display.name);
+        }
+        Ok(spec)
+    }
+
+    pub fn load(path: &str) -> anyhow::Result<Self>
+    {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read layout {}", path))?;
+        Self::parse(&text).with_context(|| format!("Cannot load layout {}", path))
+    }
+
+    // The keys the base views of the main display react to, and
+    // what they do, for the help page.
+    pub fn bindings(&self) -> Vec<(String, String)>
+    {
+        let mut bindings = Vec::new();
+        for view in &self.displays[0].views {
+            view.bindings(&mut bindings);
+        }
+        bindings
+    }
+
+    pub fn build(&self, model: &Model) -> Vec<Display>
+    {
+        self.displays.iter().enumerate().map(|(i, display)| {
+            let mut root: Box<dyn View> = Box::new(ViewContainer::new(build_all(&display.views)));
+            if i == 0 {
+                root = Box::new(Root::new(root, self.bindings()));
+            }
+            Display::new(&display.name, root, display.screen.into(), display.refresh_hz, model)
+        }).collect()
+    }
+}
+
+fn build_all(specs: &[ViewSpec]) -> Vec<Box<dyn View>>
+{
+    specs.iter().map(ViewSpec::build).collect()
+}
+
+impl ViewSpec {
+    // What a view shows, as far as the help page is concerned.
+    fn describe(&self) -> String
+    {
+        match self {
+            ViewSpec::Container { children }
+            | ViewSpec::Rows { children, .. }
+            | ViewSpec::Columns { children, .. }
+            | ViewSpec::Grid { children, .. } =>
+                children.iter().map(ViewSpec::describe).collect::<Vec<_>>().join(" / "),
+            ViewSpec::Momentary { child, .. }
+            | ViewSpec::Timed { child, .. }
+            | ViewSpec::Toggle { child, .. } => child.describe(),
+            ViewSpec::Matrix { .. } => "steps".into(),
+            ViewSpec::Volume { .. } => "volume".into(),
+            ViewSpec::Direction { .. } => "direction".into(),
+            ViewSpec::Metronome { .. } => "click".into(),
+            ViewSpec::Tap { .. } => "tap tempo".into(),
+            ViewSpec::Status { .. } => "status".into(),
+        }
+    }
+
+    fn bindings(&self, bindings: &mut Vec<(String, String)>)
+    {
+        match self {
+            ViewSpec::Container { children }
+            | ViewSpec::Rows { children, .. }
+            | ViewSpec::Columns { children, .. }
+            | ViewSpec::Grid { children, .. } => {
+                for child in children {
+                    child.bindings(bindings);
+                }
+            },
+            ViewSpec::Momentary { key, child }
+            | ViewSpec::Timed { key, child, .. }
+            | ViewSpec::Toggle { key, child, .. } => {
+                bindings.push((key.name().into(), child.describe()));
+                child.bindings(bindings);
+            },
+            ViewSpec::Matrix { mute_key, .. } =>
+                bindings.push((mute_key.name().into(), "mute mode, shift 1-8: skip".into())),
+            ViewSpec::Tap { key, .. } => bindings.push((key.name().into(), "tap tempo".into())),
+            _ => (),
+        }
+    }
+
+    fn build(&self) -> Box<dyn View>
+    {
+        match self {
+            ViewSpec::Container { children } =>
+                Box::new(ViewContainer::new(build_all(children))),
+            ViewSpec::Rows { gap, children } =>
+                Box::new(ViewContainer::new(build_all(children)).rows(*gap)),
+            ViewSpec::Columns { gap, children } =>
+                Box::new(ViewContainer::new(build_all(children)).columns(*gap)),
+            ViewSpec::Grid { columns, gap, children } =>
+                Box::new(ViewContainer::new(build_all(children)).grid(*columns, *gap)),
+            ViewSpec::Momentary { key, child } =>
+                Box::new(MomentaryView::new(child.build(), *key)),
+            ViewSpec::Timed { key, timeout_ms, child } =>
+                Box::new(MomentaryTimedView::new(child.build(), *key, Duration::from_millis(*timeout_ms))),
+            ViewSpec::Toggle { key, momentary_after_ms, child } => {
+                let view = ToggleView::new(child.build(), *key);
+                match momentary_after_ms {
+                    Some(ms) => Box::new(view.momentary_after(Duration::from_millis(*ms))),
+                    None => Box::new(view),
+                }
+            },
+            ViewSpec::Matrix { steps, mute_key } =>
+                Box::new(MatrixView::new(*steps, *mute_key)),
+            ViewSpec::Volume { place } => {
+                let view = VolumeView::default();
+                Box::new(match place { Some(place) => view.with_place(*place), None => view })
+            },
+            ViewSpec::Direction { place } => {
+                let view = DirectionView::default();
+                Box::new(match place { Some(place) => view.with_place(*place), None => view })
+            },
+            ViewSpec::Metronome { place } => {
+                let view = MetronomeView::default();
+                Box::new(match place { Some(place) => view.with_place(*place), None => view })
+            },
+            ViewSpec::Tap { key, place } => {
+                let view = TapTempoView::new(*key);
+                Box::new(match place { Some(place) => view.with_place(*place), None => view })
+            },
+            ViewSpec::Status { place } => {
+                let view = StatusView::default();
+                Box::new(match place { Some(place) => view.with_place(*place), None => view })
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_lists_the_keys_of_the_layout()
+    {
+        let spec = LayoutSpec::parse(DEFAULT_LAYOUT).unwrap();
+        let bindings = spec.bindings();
+        let keys: Vec<(&str, &str)> = bindings.iter().map(|(key, function)| (key.as_str(), function.as_str())).collect();
+        assert_eq!(keys, [
+            ("M", "mute mode, shift 1-8: skip"),
+            ("V", "volume"),
+            ("O", "direction"),
+            ("L", "click"),
+            ("T", "tap tempo"),
+        ]);
+    }
+
+    #[test]
+    fn rebound_keys_show_up_in_the_help()
+    {
+        let spec = LayoutSpec::parse(r#"
+            [[displays]]
+            name = "main"
+            [[displays.views]]
+            view = "toggle"
+            key = "X"
+            child = { view = "rows", children = [{ view = "volume" }, { view = "metronome" }] }
+        "#).unwrap();
+        assert_eq!(spec.bindings(), [("X".to_string(), "volume / click".to_string())]);
+    }
+}
@@ -0,0 +1,74 @@
+use eframe::{egui::Sense, epaint::{Rect, Vec2, Color32, vec2}, emath::Align2};
+
+use crate::view::View;
+use crate::layout::{self, Place};
+use crate::damage::{Damage, Shown};
+use crate::event::Event;
+use crate::model::{Model, BEATS_PER_BAR};
+use crate::root::{format_bpm, format_transport, beat_color};
+
+// Designed for a small 128x64 status OLED.
+const DESIGN: Vec2 = vec2(128.0, 64.0);
+
+// Tempo, transport and beat at a glance, for a status display
+// next to the main screen. Takes no input.
+pub struct StatusView {
+    place: Place,
+    rect: Rect,
+    shown: Shown<(String, String, Option<usize>)>,
+}
+
+impl Default for StatusView
+{
+    fn default() -> Self {
+        Self { place: Place::default(), rect: Rect::NOTHING, shown: Shown::default() }
+    }
+}
+
+impl StatusView {
+    pub fn with_place(mut self, place: Place) -> Self
+    {
+        self.place = place;
+        self
+    }
+}
+
+impl View for StatusView
+{
+    fn feed(&mut self, _event: Event, _model: &mut Model) -> bool {
+        false
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, model: &Model) -> eframe::egui::Response {
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter_at(rect);
+            let scale = layout::scale(rect, DESIGN);
+            let rows = layout::rows(rect.shrink(2.0 * scale), 3, 0.0);
+            painter.text(rows[0].center(), Align2::CENTER_CENTER, format_bpm(model.bpm), layout::font(20.0, scale), Color32::WHITE);
+            painter.text(rows[1].center(), Align2::CENTER_CENTER, format_transport(model), layout::font(12.0, scale), Color32::GRAY);
+            if let Some(beat) = model.beat() {
+                let beats = layout::columns(rows[2].shrink2(vec2(rows[2].width() / 4.0, 0.0)), BEATS_PER_BAR, 0.0);
+                for (i, cell) in beats.iter().enumerate() {
+                    painter.circle_filled(cell.center(), 4.0 * scale, beat_color(i, beat));
+                }
+            }
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = self.place.rect(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.shown.update((format_bpm(model.bpm), format_transport(model), model.beat())) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+}
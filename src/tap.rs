@@ -0,0 +1,124 @@
+use std::time::{Duration, Instant};
+
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Vec2, Color32, Rounding, Stroke, vec2}, emath::Align2};
+
+use crate::view::View;
+use crate::layout::{self, Place, Anchor};
+use crate::damage::{Damage, Shown};
+use crate::segment::{TextFrame, Field};
+use crate::event::Event;
+use crate::model::Model;
+use crate::root::format_bpm;
+
+// How many taps are averaged.
+const TAPS: usize = 4;
+// A longer pause starts a new measurement.
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+const OVERLAY_TIMEOUT: Duration = Duration::from_millis(1500);
+// Where the overlay goes, designed for 100x100.
+const OVERLAY: Place = Place::new(Anchor::Center, 0.3125, 0.5).offset(0.0, -0.1);
+const DESIGN: Vec2 = vec2(100.0, 100.0);
+
+// Sets the tempo from the average interval of the last few taps
+// of its key, and shows the result for a while.
+pub struct TapTempoView {
+    place: Place,
+    rect: Rect,
+    // The text of the overlay while it's up.
+    shown: Shown<Option<String>>,
+    key: Key,
+    taps: Vec<Instant>,
+    until: Instant,
+}
+
+impl TapTempoView {
+    pub fn new(key: Key) -> Self
+    {
+        Self { place: OVERLAY, rect: Rect::NOTHING, shown: Shown::default(), key, taps: vec![], until: Instant::now() }
+    }
+
+    pub fn with_place(mut self, place: Place) -> Self
+    {
+        self.place = place;
+        self
+    }
+
+    fn tap(&mut self, model: &mut Model)
+    {
+        let now = Instant::now();
+        if matches!(self.taps.last(), Some(last) if now - *last > TAP_TIMEOUT) {
+            self.taps.clear();
+        }
+        self.taps.push(now);
+        if self.taps.len() > TAPS {
+            self.taps.remove(0);
+        }
+        if let [first, .., last] = self.taps[..] {
+            let interval = (last - first).as_secs_f64() / (self.taps.len() - 1) as f64;
+            model.set_bpm(60.0 / interval);
+        }
+        self.until = now + OVERLAY_TIMEOUT;
+    }
+
+    fn overlay(&self, model: &Model) -> String
+    {
+        if self.taps.len() > 1 { format_bpm(model.bpm) } else { "TAP".into() }
+    }
+}
+
+impl View for TapTempoView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed(self.key, event.clone()) {
+            // Key repeats of a held key are no taps.
+            if self.pressed_once(self.key, event) {
+                self.tap(model);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ui(&mut self, ui: &mut eframe::egui::Ui, model: &Model) -> eframe::egui::Response {
+        if !self.active() {
+            return self.null_response(ui);
+        }
+        let rect = self.rect;
+        let response = ui.allocate_rect(rect, Sense::click());
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter_at(rect);
+            painter.rect(rect, Rounding::same(4.0), Color32::BLACK, Stroke::new(2.0_f32, Color32::WHITE));
+            painter.text(rect.center(), Align2::CENTER_CENTER, self.overlay(model), layout::font(15.0, layout::scale(rect, DESIGN)), Color32::RED);
+        }
+        response
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = self.place.rect(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        let active = self.active();
+        if self.shown.update(active.then(|| self.overlay(model))) {
+            damage.add(self.rect);
+        }
+        if active {
+            damage.wake_at(self.until);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        if self.active() && self.taps.len() > 1 {
+            frame.set(Field::Tap, format_bpm(model.bpm));
+        }
+    }
+
+    fn active(&self) -> bool {
+        self.until >= Instant::now()
+    }
+}
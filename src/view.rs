@@ -1,9 +1,14 @@
-use std::{time::{Duration, Instant}, cell::RefCell, rc::Rc};
+use std::time::{Duration, Instant};
 
-use eframe::{egui::{self, Event, Key}, epaint::Rect};
-use crate::model::Model;
+use crossbeam_channel::{unbounded, Receiver, Sender};
 
-pub trait View {
+use eframe::{egui::{self, Key}, epaint::{Rect, vec2}};
+use crate::{model::Model, led::LedFrame, segment::TextFrame, event::Event, layout};
+use crate::damage::{Damage, Shown};
+
+// Views own their children, and are Send, so a whole tree can be
+// handed to whichever thread drives it.
+pub trait View: Send {
     // Feed an event into the view. If it made use of the event,
     // the return should be true, and this signifies a stop of
     // this event's processing.
@@ -11,6 +16,39 @@ pub trait View {
     // Render the view into the UI.
     fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response;
 
+    // Render the view onto the LED pad grid. Same as with ui,
+    // views on top paint after the ones below. Views without
+    // a LED representation just leave the frame alone.
+    fn leds(&self, _frame: &mut LedFrame, _model: &Model)
+    {
+    }
+
+    // Render the view as text fields for segment or
+    // character displays. Works just like leds.
+    fn text(&self, _frame: &mut TextFrame, _model: &Model)
+    {
+    }
+
+    // Arrange the view within the rect of its parent, whenever
+    // that changes. Views place themselves in it, containers
+    // pass it on to their children.
+    fn layout(&mut self, _rect: Rect)
+    {
+    }
+
+    // Report where the view needs drawing again, because the model
+    // or its own state changed since it was drawn last. Containers
+    // ask their children.
+    fn damage(&mut self, _model: &Model, _damage: &mut Damage)
+    {
+    }
+
+    // Forget what was drawn, so the next damage covers the whole
+    // view. Parents call this when it appears or disappears.
+    fn invalidate(&mut self)
+    {
+    }
+
     // We don't really use the response mechanism, as all we react to are keys. But
     // we needt to conform to protocol, so this creates a dummy response. Seems to work.
     fn null_response(&self, ui: &mut egui::Ui) -> egui::Response {
@@ -18,6 +56,7 @@ pub trait View {
     }
 
     // Utility function to check if a given key is pressed.
+    // Held keys repeat, which suits keys that step a value.
     fn pressed(&self, candidate: Key, event: Event) -> bool {
         if let Event::Key{key, pressed, ..} = event {
             return key == candidate && pressed;
@@ -25,6 +64,15 @@ pub trait View {
         false
     }
 
+    // Like pressed, but without the repeats of a held key. For
+    // keys that toggle or open something.
+    fn pressed_once(&self, candidate: Key, event: Event) -> bool {
+        if let Event::Key{key, pressed, repeat, ..} = event {
+            return key == candidate && pressed && !repeat;
+        }
+        false
+    }
+
     // Utility function to check if a given key is involved
     fn used(&self, candidate: Key, event: Event) -> bool {
         if let Event::Key{key, ..} = event {
@@ -44,32 +92,45 @@ pub trait View {
     // In views that are toggled, this
     // method should be called before activation
     // to allow configuration.
-    fn activate(&mut self, model: &Model)
+    fn activate(&mut self, _model: &Model)
     {
     }
 
     // When becoming deactivated, the parent
     // view should inform the child about this, so
     // e.g. model updates can be applied.
-    fn deactivate(&self, model: &mut Model)
+    fn deactivate(&self, _model: &mut Model)
     {
     }
 
 }
 
+// The wrappers below show their child only while active. When that
+// changes, the child's whole area needs drawing, with or without it.
+fn damage_child(view: &mut dyn View, shown: &mut Shown<bool>, active: bool, model: &Model, damage: &mut Damage)
+{
+    if shown.update(active) {
+        view.invalidate();
+        view.damage(model, damage);
+    } else if active {
+        view.damage(model, damage);
+    }
+}
+
 pub struct MomentaryTimedView {
-    view: Rc<RefCell<dyn View>>,
+    view: Box<dyn View>,
     key: Key,
     timeout: Duration,
     until: Instant,
     deactivated: bool,
+    shown: Shown<bool>,
 }
 
 impl MomentaryTimedView {
-    pub fn new(view: Rc<RefCell<dyn View>>, key: Key, timeout: Duration) -> Self
+    pub fn new(view: Box<dyn View>, key: Key, timeout: Duration) -> Self
     {
         Self {
-            view, key, timeout, until: Instant::now(), deactivated: false
+            view, key, timeout, until: Instant::now(), deactivated: false, shown: Shown::default()
         }
     }
 }
@@ -80,49 +141,79 @@ impl View for MomentaryTimedView
     fn feed(&mut self, event: Event, model: &mut Model) -> bool {
         if self.pressed(self.key, event.clone()) {
             self.until = Instant::now() + self.timeout;
-            self.view.borrow_mut().activate(model);
+            self.view.activate(model);
             self.deactivated = false;
             return true
         } else if self.active() {
             // prolong the activation period
-            if self.view.borrow_mut().feed(event, model) {
+            if self.view.feed(event, model) {
                 self.until = Instant::now() + self.timeout;
                 return true;
             }
         }
         if !self.active() && !self.deactivated {
-            self.view.borrow_mut().deactivate(model);
+            self.view.deactivate(model);
             self.deactivated = true;
         }
-        return false;
+        false
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
         if self.active() {
-            self.view.borrow_mut().ui(ui, model)
+            self.view.ui(ui, model)
         } else {
             // A bit abusive, we just don't care..
             self.null_response(ui)
         }
     }
 
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        if self.active() {
+            self.view.leds(frame, model);
+        }
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        if self.active() {
+            self.view.text(frame, model);
+        }
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.view.layout(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        let active = self.active();
+        damage_child(self.view.as_mut(), &mut self.shown, active, model, damage);
+        if active {
+            damage.wake_at(self.until);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+        self.view.invalidate();
+    }
+
     fn active(&self) -> bool {
-        return self.until >= Instant::now()
+        self.until >= Instant::now()
     }
 }
 
 pub struct MomentaryView {
-    view: Rc<RefCell<dyn View>>,
+    view: Box<dyn View>,
     key: Key,
     active: bool,
+    shown: Shown<bool>,
 }
 
 impl MomentaryView {
 
-    pub fn new(view: Rc<RefCell<dyn View>>, key: Key) -> Self
+    pub fn new(view: Box<dyn View>, key: Key) -> Self
     {
         Self {
-            view, key, active: false
+            view, key, active: false, shown: Shown::default()
         }
     }
 }
@@ -132,45 +223,205 @@ impl View for MomentaryView
 {
     fn feed(&mut self, event: Event, model: &mut Model) -> bool {
         if self.used(self.key, event.clone()) {
-            if self.pressed(self.key, event.clone()) {
+            if self.pressed_once(self.key, event.clone()) {
                 self.active = true;
-                self.view.borrow_mut().activate(model);
+                self.view.activate(model);
+                return true
+            } else if self.pressed(self.key, event.clone()) {
+                // Repeats while held change nothing.
                 return true
             } else {
                 self.active = false;
-                self.view.borrow_mut().deactivate(model);
+                self.view.deactivate(model);
                 return true
             }
-        } if self.active {
-            return self.view.borrow_mut().feed(event, model);
+        } else if self.active {
+            return self.view.feed(event, model);
         }
-        return false;
+        false
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
         if self.active {
-            self.view.borrow_mut().ui(ui, model)
+            self.view.ui(ui, model)
         } else {
             self.null_response(ui)
         }
 
     }
 
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        if self.active {
+            self.view.leds(frame, model);
+        }
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        if self.active {
+            self.view.text(frame, model);
+        }
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.view.layout(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        damage_child(self.view.as_mut(), &mut self.shown, self.active, model, damage);
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+        self.view.invalidate();
+    }
+
     fn active(&self) -> bool {
         self.active
     }
 
 }
 
+pub struct ToggleView {
+    view: Box<dyn View>,
+    key: Key,
+    active: bool,
+    // When the key is held longer than this, the view closes
+    // again on release, like a MomentaryView.
+    momentary_after: Option<Duration>,
+    // When the key went down, if that press opened us.
+    opened: Option<Instant>,
+    shown: Shown<bool>,
+}
+
+impl ToggleView {
+
+    // Latches on the first press, and off on the second.
+    pub fn new(view: Box<dyn View>, key: Key) -> Self
+    {
+        Self {
+            view, key, active: false, momentary_after: None, opened: None, shown: Shown::default()
+        }
+    }
+
+    // Latch on a short press, but stay momentary when held longer
+    // than the threshold.
+    pub fn momentary_after(mut self, threshold: Duration) -> Self
+    {
+        self.momentary_after = Some(threshold);
+        self
+    }
+}
+
+
+impl View for ToggleView
+{
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        if self.pressed_once(self.key, event.clone()) {
+            self.active = !self.active;
+            if self.active {
+                self.opened = Some(Instant::now());
+                self.view.activate(model);
+            } else {
+                self.opened = None;
+                self.view.deactivate(model);
+            }
+            return true
+        } else if self.pressed(self.key, event.clone()) {
+            // Repeats while held would toggle us back and forth.
+            return true
+        } else if self.used(self.key, event.clone()) {
+            if let (Some(opened), Some(threshold)) = (self.opened.take(), self.momentary_after) {
+                if opened.elapsed() >= threshold {
+                    self.active = false;
+                    self.view.deactivate(model);
+                }
+            }
+            return true
+        } else if self.active {
+            return self.view.feed(event, model);
+        }
+        false
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
+        if self.active {
+            self.view.ui(ui, model)
+        } else {
+            self.null_response(ui)
+        }
+    }
+
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        if self.active {
+            self.view.leds(frame, model);
+        }
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        if self.active {
+            self.view.text(frame, model);
+        }
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.view.layout(rect);
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        damage_child(self.view.as_mut(), &mut self.shown, self.active, model, damage);
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+        self.view.invalidate();
+    }
+
+    fn active(&self) -> bool {
+        self.active
+    }
+}
+
+// How a ViewContainer hands out its rect to the children.
+enum Arrangement {
+    // All children share the whole rect, drawn on top of each other.
+    Overlay,
+    Rows(f32),
+    Columns(f32),
+    // Row by row, with as many rows as needed.
+    Grid(usize, f32),
+}
+
 pub struct ViewContainer {
-    views: Vec<Rc<RefCell<dyn View>>>
+    views: Vec<Box<dyn View>>,
+    arrangement: Arrangement,
 }
 
 impl ViewContainer {
 
-    pub fn new(views: Vec<Rc<RefCell<dyn View>>>) -> Self
+    pub fn new(views: Vec<Box<dyn View>>) -> Self
     {
-        Self{ views }
+        Self{ views, arrangement: Arrangement::Overlay }
+    }
+
+    // Stack the children top to bottom, with a gap between them.
+    pub fn rows(mut self, gap: f32) -> Self
+    {
+        self.arrangement = Arrangement::Rows(gap);
+        self
+    }
+
+    // Line the children up left to right, with a gap between them.
+    pub fn columns(mut self, gap: f32) -> Self
+    {
+        self.arrangement = Arrangement::Columns(gap);
+        self
+    }
+
+    // Fill the children into cells, row by row.
+    pub fn grid(mut self, columns: usize, gap: f32) -> Self
+    {
+        self.arrangement = Arrangement::Grid(columns.max(1), gap);
+        self
     }
 }
 
@@ -179,7 +430,7 @@ impl View for ViewContainer
 {
     fn feed(&mut self, event: Event, model: &mut Model) -> bool {
         for view in &mut self.views {
-            if view.borrow_mut().feed(event.clone(), model)
+            if view.feed(event.clone(), model)
             {
                 return true;
             }
@@ -189,36 +440,74 @@ impl View for ViewContainer
 
     fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
         for view in &mut self.views {
-            view.borrow_mut().ui(ui, model);
+            view.ui(ui, model);
         }
         self.null_response(ui)
     }
 
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        for view in &self.views {
+            view.leds(frame, model);
+        }
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        for view in &self.views {
+            view.text(frame, model);
+        }
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        let count = self.views.len();
+        let rects = match self.arrangement {
+            Arrangement::Overlay => vec![rect; count],
+            Arrangement::Rows(gap) => layout::rows(rect, count, gap),
+            Arrangement::Columns(gap) => layout::columns(rect, count, gap),
+            Arrangement::Grid(columns, gap) =>
+                layout::grid(rect, columns, count.div_ceil(columns), vec2(gap, gap)),
+        };
+        for (view, rect) in self.views.iter_mut().zip(rects) {
+            view.layout(rect);
+        }
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        for view in &mut self.views {
+            view.damage(model, damage);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        for view in &mut self.views {
+            view.invalidate();
+        }
+    }
+
     fn active(&self) -> bool {
-        self.views.iter().any(|view| { view.borrow().active() })
+        self.views.iter().any(|view| { view.active() })
     }
 
 }
 
 pub struct MutexViewContainer {
-    pub views: Vec<Rc<RefCell<dyn View>>>
+    pub views: Vec<Box<dyn View>>
 }
 
 impl MutexViewContainer {
-    fn active_view(&self) -> Option<Rc<RefCell<dyn View>>>
+    fn active_view(&self) -> Option<&dyn View>
     {
-        for view in &self.views {
-            if view.borrow().active() {
-                return Some(view.clone())
-            }
-        }
-        None
+        self.views.iter().find(|view| view.active()).map(|view| view.as_ref())
+    }
+
+    fn active_view_mut(&mut self) -> Option<&mut Box<dyn View>>
+    {
+        self.views.iter_mut().find(|view| view.active())
     }
 }
 impl View for MutexViewContainer {
     fn feed(&mut self, event: Event, model: &mut Model) -> bool {
-        for view in &self.views {
-            if view.borrow_mut().feed(event.clone(), model) {
+        for view in &mut self.views {
+            if view.feed(event.clone(), model) {
                 return true
             }
         }
@@ -226,13 +515,312 @@ impl View for MutexViewContainer {
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
-        if let Some(view) =  self.active_view() {
-            return view.borrow_mut().ui(ui, model)
+        if let Some(view) = self.active_view_mut() {
+            return view.ui(ui, model)
         }
         self.null_response(ui)
     }
 
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        if let Some(view) =  self.active_view() {
+            view.leds(frame, model);
+        }
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        if let Some(view) =  self.active_view() {
+            view.text(frame, model);
+        }
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        for view in &mut self.views {
+            view.layout(rect);
+        }
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        for view in &mut self.views {
+            view.damage(model, damage);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        for view in &mut self.views {
+            view.invalidate();
+        }
+    }
+
     fn active(&self) -> bool {
-        self.views.iter().any(|view| { view.borrow().active() })
+        self.views.iter().any(|view| { view.active() })
+    }
+}
+
+enum ModalRequest {
+    Push(Box<dyn View>),
+    Pop,
+}
+
+// Lets views inside of a ModalStack put another modal view on
+// top, like a page asking for confirmation, or close the topmost
+// one, e.g. themselves. Requests take effect after the current event.
+#[derive(Clone)]
+pub struct ModalHandle {
+    requests: Sender<ModalRequest>,
+}
+
+impl ModalHandle {
+    pub fn push(&self, view: Box<dyn View>)
+    {
+        let _ = self.requests.send(ModalRequest::Push(view));
+    }
+
+    pub fn pop(&self)
+    {
+        // The stack owns the receiver, and lives as long as the tree.
+        let _ = self.requests.send(ModalRequest::Pop);
+    }
+}
+
+// A base view with a stack of modal views on top. The topmost
+// modal view gets all input exclusively, until it's popped,
+// either by a Back event, or through a ModalHandle.
+pub struct ModalStack {
+    base: Box<dyn View>,
+    // Where modal views get laid out when pushed.
+    rect: Rect,
+    // A modal view went away, what was below needs drawing again.
+    popped: bool,
+    stack: Vec<Box<dyn View>>,
+    handle: ModalHandle,
+    requests: Receiver<ModalRequest>,
+}
+
+impl ModalStack {
+    pub fn new(base: Box<dyn View>) -> Self
+    {
+        let (sender, requests) = unbounded();
+        Self {
+            base, rect: Rect::NOTHING, popped: false, stack: vec![], handle: ModalHandle { requests: sender }, requests
+        }
+    }
+
+    pub fn handle(&self) -> ModalHandle
+    {
+        self.handle.clone()
+    }
+
+    pub fn push(&mut self, mut view: Box<dyn View>, model: &Model)
+    {
+        view.layout(self.rect);
+        view.activate(model);
+        self.stack.push(view);
+    }
+
+    fn pop(&mut self, model: &mut Model)
+    {
+        if let Some(view) = self.stack.pop() {
+            view.deactivate(model);
+            self.popped = true;
+        }
+    }
+
+    fn process_requests(&mut self, model: &mut Model)
+    {
+        while let Ok(request) = self.requests.try_recv() {
+            match request {
+                ModalRequest::Push(view) => self.push(view, model),
+                ModalRequest::Pop => self.pop(model),
+            }
+        }
+    }
+}
+
+impl View for ModalStack {
+    fn feed(&mut self, event: Event, model: &mut Model) -> bool {
+        let res = match self.stack.last_mut() {
+            Some(_) if event == Event::Back => {
+                self.pop(model);
+                true
+            }
+            Some(top) => {
+                // Exclusive: whatever the top doesn't want, nobody gets.
+                top.feed(event, model);
+                true
+            }
+            None => self.base.feed(event, model),
+        };
+        self.process_requests(model);
+        res
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, model: &Model) -> egui::Response {
+        let response = self.base.ui(ui, model);
+        for view in &mut self.stack {
+            view.ui(ui, model);
+        }
+        response
+    }
+
+    fn leds(&self, frame: &mut LedFrame, model: &Model) {
+        self.base.leds(frame, model);
+        for view in &self.stack {
+            view.leds(frame, model);
+        }
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &Model) {
+        self.base.text(frame, model);
+        for view in &self.stack {
+            view.text(frame, model);
+        }
+    }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.base.layout(rect);
+        for view in &mut self.stack {
+            view.layout(rect);
+        }
+    }
+
+    fn damage(&mut self, model: &Model, damage: &mut Damage) {
+        if self.popped {
+            self.popped = false;
+            damage.add(self.rect);
+            self.invalidate();
+        }
+        self.base.damage(model, damage);
+        for view in &mut self.stack {
+            view.damage(model, damage);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.base.invalidate();
+        for view in &mut self.stack {
+            view.invalidate();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Modifiers;
+    use crate::dialog::ToastView;
+    use crate::pattern::PatternView;
+    use crate::help::HelpView;
+    use crate::volume::VolumeView;
+    use eframe::epaint::Pos2;
+
+    const SCREEN: Rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(320.0, 200.0));
+
+    fn collect(view: &mut dyn View, model: &Model) -> Damage
+    {
+        let mut damage = Damage::default();
+        view.damage(model, &mut damage);
+        damage
+    }
+
+    fn press(stack: &mut ModalStack, key: Key, model: &mut Model)
+    {
+        for pressed in [true, false] {
+            stack.feed(self::key(key, pressed, false), model);
+        }
+    }
+
+    fn key(key: Key, pressed: bool, repeat: bool) -> Event
+    {
+        Event::Key { key, pressed, repeat, modifiers: Modifiers::default() }
+    }
+
+    #[test]
+    fn key_repeats_dont_toggle()
+    {
+        let mut model = Model::default();
+        let mut toggle = ToggleView::new(Box::new(ViewContainer::new(vec![])), Key::M);
+        toggle.feed(key(Key::M, true, false), &mut model);
+        for _ in 0..3 {
+            assert!(toggle.feed(key(Key::M, true, true), &mut model));
+            assert!(toggle.active());
+        }
+        toggle.feed(key(Key::M, false, false), &mut model);
+        assert!(toggle.active());
+        toggle.feed(key(Key::M, true, false), &mut model);
+        assert!(!toggle.active());
+
+        let mut momentary = MomentaryView::new(Box::new(ViewContainer::new(vec![])), Key::Num1);
+        momentary.feed(key(Key::Num1, true, false), &mut model);
+        momentary.feed(key(Key::Num1, true, true), &mut model);
+        assert!(momentary.active());
+        momentary.feed(key(Key::Num1, false, false), &mut model);
+        assert!(!momentary.active());
+    }
+
+    #[test]
+    fn pages_push_confirmations_on_top_of_themselves()
+    {
+        let mut model = Model::default();
+        let toasts = ToastView::default();
+        let mut stack = ModalStack::new(Box::new(ViewContainer::new(vec![])));
+        stack.push(Box::new(PatternView::new(stack.handle(), toasts.handle())), &model);
+
+        // Dicing asks first, Back only leaves the question.
+        press(&mut stack, Key::D, &mut model);
+        assert_eq!(stack.stack.len(), 2);
+        stack.feed(Event::Back, &mut model);
+        assert_eq!(stack.stack.len(), 1);
+
+        // Answering yes dices, and leads back to the page.
+        model.density = 1.0;
+        press(&mut stack, Key::D, &mut model);
+        press(&mut stack, Key::ArrowRight, &mut model);
+        assert_eq!(stack.stack.len(), 1);
+        assert!(model.notes.iter().all(Option::is_some));
+
+        // Which still takes its keys, and closes on its own.
+        press(&mut stack, Key::E, &mut model);
+        assert!(stack.stack.is_empty());
+    }
+
+    #[test]
+    fn timed_overlays_wake_up_to_go_away()
+    {
+        let mut model = Model::default();
+        let mut timed = MomentaryTimedView::new(Box::new(VolumeView::default()), Key::V, Duration::from_secs(1));
+        timed.layout(SCREEN);
+        // Everything is new at first, hidden or not.
+        collect(&mut timed, &model);
+        assert!(collect(&mut timed, &model).rects().is_empty());
+
+        // Showing up draws the overlay, and asks to be woken when it's over.
+        timed.feed(key(Key::V, true, false), &mut model);
+        let shown = collect(&mut timed, &model);
+        assert_eq!(shown.rects().len(), 1);
+        assert_eq!(shown.wake(), Some(timed.until));
+        assert!(collect(&mut timed, &model).rects().is_empty());
+
+        // Once it's over, the whole overlay needs drawing again.
+        timed.until = Instant::now() - Duration::from_millis(1);
+        let gone = collect(&mut timed, &model);
+        assert_eq!(gone.rects(), shown.rects());
+        assert_eq!(gone.wake(), None);
+        assert!(collect(&mut timed, &model).rects().is_empty());
+    }
+
+    #[test]
+    fn popping_a_page_damages_the_whole_stack()
+    {
+        let mut model = Model::default();
+        let mut stack = ModalStack::new(Box::new(ViewContainer::new(vec![])));
+        stack.layout(SCREEN);
+        stack.push(Box::new(HelpView::new(stack.handle(), vec![])), &model);
+        assert_eq!(collect(&mut stack, &model).rects(), [SCREEN]);
+        assert!(collect(&mut stack, &model).rects().is_empty());
+
+        stack.feed(Event::Back, &mut model);
+        assert_eq!(collect(&mut stack, &model).rects(), [SCREEN]);
+        assert!(collect(&mut stack, &model).rects().is_empty());
     }
 }
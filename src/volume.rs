@@ -1,26 +1,72 @@
-use eframe::{egui::{Key, Sense}, epaint::{Rect, FontId, Color32, Rounding, Stroke}, emath::Align2};
+use eframe::{egui::{Key, Sense}, epaint::{Rect, Vec2, Color32, Rounding, Stroke, vec2}, emath::Align2};
 
 use crate::view::View;
+use crate::layout::{self, Place, Anchor};
+use crate::damage::{Damage, Shown};
+use crate::segment::{TextFrame, Field};
+use crate::event::{Event, Acceleration, Curve, MAIN_ENCODER};
+use crate::model::MAX_VOLUME;
 
-pub struct VolumeView {
+// The range is small, so this only needs to speed up a little.
+const VOLUME_ACCELERATION: Acceleration = Acceleration {
+    curve: Curve::Linear,
+    step: 1.0,
+    fine_step: 0.1,
+    threshold: 8.0,
+    gain: 0.1,
+    max_factor: 3.0,
+};
+
+// A box a bit above the middle of the screen, designed for 100x100.
+const OVERLAY: Place = Place::new(Anchor::Center, 0.3125, 0.5).offset(0.0, -0.1);
+const DESIGN: Vec2 = vec2(100.0, 100.0);
+
+fn format_volume(volume: f64) -> String
+{
+    format!("{:.1}", volume)
+}
 
+pub struct VolumeView {
+    place: Place,
+    rect: Rect,
+    shown: Shown<f64>,
 }
 
 impl Default for VolumeView
 {
     fn default() -> Self {
-        Self {  }
+        Self { place: OVERLAY, rect: Rect::NOTHING, shown: Shown::default() }
+    }
+}
+
+impl VolumeView {
+    pub fn with_place(mut self, place: Place) -> Self
+    {
+        self.place = place;
+        self
     }
 }
 
 impl View for VolumeView
 {
-    fn feed(&mut self, event: eframe::egui::Event, model: &mut crate::model::Model) -> bool {
+    fn feed(&mut self, event: Event, model: &mut crate::model::Model) -> bool {
         if self.pressed(Key::ArrowLeft, event.clone()) {
-            model.receive_complaint();
+            if event.modifiers().shift {
+                model.change_volume(-MAX_VOLUME);
+            } else {
+                model.receive_complaint();
+            }
             true
         } else if self.pressed(Key::ArrowRight, event.clone()) {
-            model.pumpup();
+            if event.modifiers().shift {
+                // These go to eleven.
+                model.change_volume(MAX_VOLUME);
+            } else {
+                model.pumpup();
+            }
+            true
+        } else if let Event::Encoder { id: MAIN_ENCODER, delta, velocity, modifiers } = event {
+            model.change_volume(VOLUME_ACCELERATION.change(delta, velocity, modifiers));
             true
         } else {
             false
@@ -33,14 +79,31 @@ impl View for VolumeView
         let red = Color32::from_rgba_unmultiplied(255, 0, 0, 255);
         let rounding = Rounding::same(4.0);
 
-        let rect = Rect::from_min_max((0.0, 0.0).into(), (320.0, 160.0).into());
+        let rect = self.rect;
         let response = ui.allocate_rect(rect, Sense::click());
         if ui.is_rect_visible(rect) {
             let painter = ui.painter_at(rect);
-            painter.rect(
-                Rect::from_center_size(rect.center(), (100.0, 100.0).into()), rounding, black, Stroke::new(2.0, white));
-            painter.text(rect.center(), Align2::CENTER_CENTER, format!("{:}", model.volume), FontId::monospace(15.0), red);
+            painter.rect(rect, rounding, black, Stroke::new(2.0_f32, white));
+            painter.text(rect.center(), Align2::CENTER_CENTER, format_volume(model.volume), layout::font(15.0, layout::scale(rect, DESIGN)), red);
         }
         response
     }
+
+    fn layout(&mut self, rect: Rect) {
+        self.rect = self.place.rect(rect);
+    }
+
+    fn damage(&mut self, model: &crate::model::Model, damage: &mut Damage) {
+        if self.shown.update(model.volume) {
+            damage.add(self.rect);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.shown.forget();
+    }
+
+    fn text(&self, frame: &mut TextFrame, model: &crate::model::Model) {
+        frame.set(Field::Volume, format_volume(model.volume));
+    }
 }